@@ -7,24 +7,109 @@ use ggez::{
 };
 use std::collections::HashMap;
 use rand::Rng;
-use crate::map::Tree;
+use serde::Deserialize;
+use crate::map::Map;
 use crate::particle::Particle;
+use crate::utils::{ segment_intersects_rect, game_rng };
+
+// How long each step of the attack lasts, in seconds
+const WIND_UP_TIME: f32 = 0.4;
+const STRIKE_TIME: f32 = 0.15;
+const RECOVERY_TIME: f32 = 0.35;
+const ATTACK_COOLDOWN: f32 = 1.0;
+// Distance (between the centers) under which Bertrand starts winding up
+const ATTACK_RANGE: f32 = 14.0;
+// How far in front of him the strike reaches
+const ATTACK_REACH: f32 = 8.0;
+
+// How far Bertrand can see, if there's no tree in the way
+const SIGHT_RADIUS: f32 = 140.0;
+// How far Bertrand can hear a gunshot from
+const HEARING_RADIUS: f32 = 300.0;
+// At night Bertrand can't see as far, but he hears better and he's faster
+// (at full darkness, less in between)
+const NIGHT_SIGHT_LOSS: f32 = 0.35;
+const NIGHT_HEARING_BONUS: f32 = 0.5;
+const NIGHT_SPEED_BONUS: f32 = 0.2;
+// Under this distance, Bertrand doesn't need to wonder if it's really Fabien
+const INSTANT_SPOT_RADIUS: f32 = 30.0;
+// How long Bertrand has to look at Fabien before chasing him
+const SUSPICION_TIME: f32 = 0.6;
+// How long Bertrand looks for Fabien before giving up
+const SEARCH_TIME: f32 = 5.0;
+// How far from his spawn point Bertrand wanders when he's idle
+const WANDER_RADIUS: f32 = 60.0;
+const WANDER_SPEED_FACTOR: f32 = 0.4;
+
+// How often a chasing Bertrand works out his way to Fabien again, since he moves
+const REPATH_TIME: f32 = 0.5;
+
+// How long Bertrand takes to fall down when he's shot
+const FALL_TIME: f32 = 0.3;
+// How long his body stays on the ground (fading out) after that
+const CORPSE_TIME: f32 = 2.0;
+
+// The different kinds of Bertrand, for now they all are the same old Bertrand
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum BertrandKind {
+    Regular
+}
+
+// Bertrand's attack cycle. He walks towards Fabien, raises his arm for a bit
+// (which gives Fabien time to dodge), strikes, and needs some time to recover.
+#[derive(Clone, Copy, PartialEq)]
+enum AttackState {
+    Approaching,
+    WindUp(f32),
+    Strike(f32),
+    Recovery(f32)
+}
+
+// What Bertrand knows about Fabien
+#[derive(Clone, Copy, PartialEq)]
+enum Awareness {
+    // Walking around without a care in the world
+    Idle,
+    // Saw something, and is staring at it (for how long)
+    Suspicious(f32),
+    Chasing,
+    // Lost track of Fabien or heard something, going to the last known
+    // position and looking around (for how long)
+    Searching(f32)
+}
 
 pub struct Bertrand {
+    kind: BertrandKind,
     sprites: HashMap<String, graphics::Image>,
     facing: String,
     animation_cycle: u8,
     animation_time: f32,
-    swinging: (bool, f32),
+    attack: AttackState,
+    attack_cooldown: f32,
+    strike_landed: bool,
+    dying: Option<f32>,
     hitbox: Rect,
+    awareness: Awareness,
+    last_known_pos: Point2<f32>,
+    home: Point2<f32>,
     objective: Point2<f32>,
-    is_in_tree: bool,
+    // The way to Fabien when he's chasing him, the next step last
+    path: Vec<Point2<f32>>,
+    repath_time: f32,
+    in_bush: bool,
     particles: Vec<Particle>,
-    speed: f32
+    speed: f32,
+    // How dark it is, see lighting.rs
+    darkness: f32,
+    // The wave he came with, only those count to clear a wave
+    wave: u32
 }
 
 impl Bertrand {
-    pub fn new(ctx: &mut Context, hitbox: Rect) -> GameResult<Bertrand> {
+    // He comes looking for Fabien where he was when he spawned, he'd never find
+    // him otherwise (he spawns too far to see him and only wanders a bit)
+    pub fn new(ctx: &mut Context, kind: BertrandKind, hitbox: Rect, speed_factor: f32, wave: u32,
+               fabien_pos: Point2<f32>) -> GameResult<Bertrand> {
         let mut sprites = HashMap::new();
 
         for facing in ["front", "back", "right", "left"].iter() {
@@ -35,39 +120,97 @@ impl Bertrand {
         }
 
         let bertrand = Bertrand {
+            kind: kind,
             sprites: sprites,
             facing: "front".to_string(),
             hitbox: hitbox,
             animation_cycle: 0,
             animation_time: 0.0,
-            swinging: (false, 0.0),
-            objective: Point2::new(0.0, 0.0),
-            is_in_tree: false,
+            attack: AttackState::Approaching,
+            attack_cooldown: 0.0,
+            strike_landed: false,
+            dying: None,
+            awareness: Awareness::Searching(0.0),
+            last_known_pos: fabien_pos,
+            home: Point2::new(hitbox.x, hitbox.y),
+            objective: fabien_pos,
+            path: vec![],
+            repath_time: 0.0,
+            in_bush: false,
             particles: vec![],
-            speed: 75.0
+            speed: 75.0 * speed_factor,
+            darkness: 0.0,
+            wave: wave
         };
 
         Ok(bertrand)
     }
 
     pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        if let Some(time) = self.dying {
+            return self.draw_corpse(ctx, time);
+        }
+
         let dt = ggez::timer::delta(ctx).as_secs_f32();
-        if !self.swinging.0 {
-            self.animation_time += dt;
-            if self.animation_time > 1.0 / 6.0 {
-                self.animation_time = 0.0;
-                self.animation_cycle = (self.animation_cycle + 1) % 4;
+        let mut color = graphics::WHITE;
+        match self.attack {
+            AttackState::Approaching if self.is_standing_still() => self.animation_cycle = 0,
+            AttackState::Approaching => {
+                self.animation_time += dt;
+                if self.animation_time > 1.0 / 6.0 {
+                    self.animation_time = 0.0;
+                    self.animation_cycle = (self.animation_cycle + 1) % 4;
+                }
+            },
+            AttackState::WindUp(time) => {
+                self.animation_cycle = 4;
+                // Flash red while winding up so the player knows a hit is coming
+                let flash = ((time * 20.0).sin() + 1.0) / 2.0;
+                color = graphics::Color::new(1.0, 1.0 - flash * 0.6, 1.0 - flash * 0.6, 1.0);
+            },
+            AttackState::Strike(_) => self.animation_cycle = 5,
+            AttackState::Recovery(time) => {
+                self.animation_cycle = if time < RECOVERY_TIME / 2.0 { 5 } else { 0 };
             }
-        } else {
-            if self.swinging.1 < 0.25 { self.animation_cycle = 4; }
-            else { self.animation_cycle = 5; }
-
-            self.swinging.1 += dt;
         }
 
         let sprite = self.sprites.get(&format!("{}_{}", self.facing, self.animation_cycle)).unwrap();
         let param = DrawParam::default()
-            .dest(Point2::new(self.hitbox.x - 3.0, self.hitbox.y));
+            .dest(Point2::new(self.hitbox.x - 3.0, self.hitbox.y))
+            .color(color);
+        graphics::draw(ctx, sprite, param)?;
+
+        // A little mark above his head when he's onto something
+        let mark_color = match self.awareness {
+            Awareness::Suspicious(_) => Some(graphics::Color::from_rgb(255, 220, 40)),
+            Awareness::Searching(_) => Some(graphics::Color::from_rgb(255, 140, 20)),
+            _ => None
+        };
+        if let Some(color) = mark_color {
+            let mark = graphics::MeshBuilder::new()
+                .rectangle(graphics::DrawMode::fill(), Rect::new(0.0, 0.0, 2.0, 4.0), color)
+                .rectangle(graphics::DrawMode::fill(), Rect::new(0.0, 5.0, 2.0, 2.0), color)
+                .build(ctx)?;
+            graphics::draw(ctx, &mark, (Point2::new(self.hitbox.x + self.hitbox.w / 2.0 - 1.0, self.hitbox.y - 9.0),))?;
+        }
+
+        for p in self.particles.iter() { p.draw(ctx)?; }
+
+        Ok(())
+    }
+
+    // Bertrand falls on his side and slowly fades away
+    fn draw_corpse(&self, ctx: &mut Context, time: f32) -> GameResult {
+        let fall = (time / FALL_TIME).min(1.0);
+        let alpha = if time < FALL_TIME { 1.0 } else { 1.0 - (time - FALL_TIME) / CORPSE_TIME };
+        let side = if self.facing == "left" { -1.0 } else { 1.0 };
+
+        let sprite = self.sprites.get(&format!("{}_0", self.facing)).unwrap();
+        let param = DrawParam::default()
+            .dest(Point2::new(self.hitbox.x + self.hitbox.w / 2.0, self.hitbox.y + self.hitbox.h))
+            .offset(Point2::new(0.5, 1.0))
+            .rotation(side * fall * std::f32::consts::FRAC_PI_2)
+            .color(graphics::Color::new(1.0, 1.0 - fall * 0.3, 1.0 - fall * 0.3, alpha.max(0.0)));
         graphics::draw(ctx, sprite, param)?;
 
         for p in self.particles.iter() { p.draw(ctx)?; }
@@ -75,70 +218,216 @@ impl Bertrand {
         Ok(())
     }
 
-    pub fn update(&mut self, ctx: &mut Context, fabien_hitbox: Rect, trees: &Vec<Tree>) -> GameResult {
+    pub fn update(&mut self, ctx: &mut Context, fabien_hitbox: Rect, map: &Map, darkness: f32) -> GameResult {
         let dt = ggez::timer::delta(ctx).as_secs_f32();
+        self.darkness = darkness;
 
-        if self.swinging.0 { 
+        if let Some(ref mut time) = self.dying {
+            *time += dt;
             for p in self.particles.iter_mut() { p.update(ctx); }
             self.particles.retain(|p| !p.is_dead());
             return Ok(());
         }
 
-        let was_in_tree = self.is_in_tree;
-        self.is_in_tree = false;
-        for tree in trees.iter() {
-            if self.hitbox.overlaps(&tree.get_hitbox()) && !was_in_tree {
-                self.is_in_tree = true;
-                self.spawn_leaf_particles(ctx)?;
-                self.speed /= 8.0;
-                break;
-            }
+        if self.attack_cooldown > 0.0 { self.attack_cooldown -= dt; }
+        self.update_attack(dt, fabien_hitbox);
+
+        // Bertrand stands still while he's attacking
+        if self.attack != AttackState::Approaching {
+            for p in self.particles.iter_mut() { p.update(ctx); }
+            self.particles.retain(|p| !p.is_dead());
+            return Ok(());
         }
 
-        if was_in_tree && !self.is_in_tree {
-            self.spawn_leaf_particles(ctx)?; 
-            self.speed *= 8.0;
+        // The leaves rustle when he goes in or out of a bush
+        let was_in_bush = self.in_bush;
+        self.in_bush = map.hides(self.hitbox);
+        if self.in_bush != was_in_bush { self.spawn_leaf_particles(ctx)?; }
+
+        let fabien_pos = Point2::new(fabien_hitbox.x, fabien_hitbox.y);
+        let sees_fabien = self.can_see(fabien_hitbox, map);
+        self.update_awareness(dt, sees_fabien, fabien_pos);
+
+        match self.awareness {
+            Awareness::Idle => {
+                // Pick somewhere else to go once he got where he wanted
+                if self.hitbox.x == self.objective.x && self.hitbox.y == self.objective.y {
+                    self.objective = game_rng(|rng| Point2::new(
+                        self.home.x + rng.gen_range(-WANDER_RADIUS..WANDER_RADIUS),
+                        self.home.y + rng.gen_range(-WANDER_RADIUS..WANDER_RADIUS)
+                    ));
+                }
+                // Somewhere he can't go, he'll pick another spot next time
+                if !self.go_to(ctx, self.objective, self.speed * WANDER_SPEED_FACTOR, map) {
+                    self.objective = Point2::new(self.hitbox.x, self.hitbox.y);
+                }
+            },
+            Awareness::Suspicious(_) => self.face(fabien_pos),
+            Awareness::Chasing => self.chase(ctx, fabien_pos, map),
+            Awareness::Searching(_) => {
+                // Once he's where Fabien was, look around the spot
+                if self.hitbox.x == self.objective.x && self.hitbox.y == self.objective.y {
+                    self.objective = game_rng(|rng| Point2::new(
+                        self.last_known_pos.x + rng.gen_range(-WANDER_RADIUS..WANDER_RADIUS) / 2.0,
+                        self.last_known_pos.y + rng.gen_range(-WANDER_RADIUS..WANDER_RADIUS) / 2.0
+                    ));
+                }
+                if !self.go_to(ctx, self.objective, self.speed, map) {
+                    self.objective = Point2::new(self.hitbox.x, self.hitbox.y);
+                }
+            }
         }
 
-        let fabien_pos = (fabien_hitbox.x, fabien_hitbox.y);
-        let next_move = self.speed * dt;
+        for p in self.particles.iter_mut() { p.update(ctx); }
+        self.particles.retain(|p| !p.is_dead());
 
-        if (self.hitbox.x < fabien_pos.0 + 30.0 &&
-            self.hitbox.x > fabien_pos.0 - 30.0) &&
-           (self.hitbox.y < fabien_pos.1 + 30.0 &&
-            self.hitbox.y > fabien_pos.1 - 30.0)
-        {
-            self.move_towards(ctx, fabien_pos);
+        Ok(())
+    }
 
-            if self.hitbox.x < fabien_pos.0 + next_move && self.hitbox.x > fabien_pos.0 - next_move {
-                self.hitbox.x = fabien_pos.0;
-            }
-            if self.hitbox.y < fabien_pos.1 + next_move && self.hitbox.y > fabien_pos.1 - next_move {
-                self.hitbox.y = fabien_pos.1;
-            }
-        } else {
-            if self.animation_cycle == 0 {
-                self.objective = Point2::new(fabien_pos.0, fabien_pos.1);
-            }
+    fn is_standing_still(&self) -> bool {
+        matches!(self.awareness, Awareness::Suspicious(_))
+    }
 
-            self.move_towards(ctx, (self.objective.x, self.objective.y));
+    fn can_see(&self, fabien_hitbox: Rect, map: &Map) -> bool {
+        let eyes = Point2::new(self.hitbox.x + self.hitbox.w / 2.0, self.hitbox.y + self.hitbox.h / 2.0);
+        let fabien = Point2::new(fabien_hitbox.x + fabien_hitbox.w / 2.0, fabien_hitbox.y + fabien_hitbox.h / 2.0);
 
-            if self.hitbox.x < self.objective.x + next_move && self.hitbox.x > self.objective.x - next_move {
-                self.hitbox.x = self.objective.x;
+        let distance = (fabien - eyes).norm();
+        if distance > SIGHT_RADIUS * (1.0 - NIGHT_SIGHT_LOSS * self.darkness) { return false; }
+        // Hiding in a bush works, unless Bertrand is right next to it
+        if map.hides(fabien_hitbox) && distance > INSTANT_SPOT_RADIUS { return false; }
+
+        for obstacle in map.get_obstacles().iter().filter(|obstacle| obstacle.get_kind().blocks_sight()) {
+            let sight_box = obstacle.get_sight_box();
+            // Bertrand can still see out of the tree he's in
+            if sight_box.contains(eyes) { continue; }
+            if segment_intersects_rect(eyes, fabien, sight_box) { return false; }
+        }
+
+        true
+    }
+
+    fn update_awareness(&mut self, dt: f32, sees_fabien: bool, fabien_pos: Point2<f32>) {
+        if sees_fabien { self.last_known_pos = fabien_pos; }
+        let close = (fabien_pos - Point2::new(self.hitbox.x, self.hitbox.y)).norm() < INSTANT_SPOT_RADIUS;
+
+        self.awareness = match self.awareness {
+            Awareness::Idle => {
+                if sees_fabien && close { Awareness::Chasing }
+                else if sees_fabien { Awareness::Suspicious(0.0) }
+                else { Awareness::Idle }
+            },
+            Awareness::Suspicious(time) => {
+                if sees_fabien && (close || time + dt > SUSPICION_TIME) { Awareness::Chasing }
+                else if sees_fabien { Awareness::Suspicious(time + dt) }
+                else { self.start_searching() }
+            },
+            Awareness::Chasing => {
+                if sees_fabien { Awareness::Chasing }
+                else { self.start_searching() }
+            },
+            Awareness::Searching(time) => {
+                if sees_fabien { Awareness::Chasing }
+                else if time + dt > SEARCH_TIME {
+                    // Give up and stay around here
+                    self.home = self.last_known_pos;
+                    Awareness::Idle
+                } else { Awareness::Searching(time + dt) }
             }
-            if self.hitbox.y < self.objective.y + next_move && self.hitbox.y > self.objective.y - next_move {
-                self.hitbox.y = self.objective.y;
+        };
+    }
+
+    fn start_searching(&mut self) -> Awareness {
+        self.objective = self.last_known_pos;
+        Awareness::Searching(0.0)
+    }
+
+    // Fabien shot somewhere, Bertrand goes to see what's going on if he heard it
+    pub fn hear_noise(&mut self, pos: Point2<f32>) {
+        if self.awareness == Awareness::Chasing || self.is_dying() { return; }
+        let hearing_radius = HEARING_RADIUS * (1.0 + NIGHT_HEARING_BONUS * self.darkness);
+        if (pos - Point2::new(self.hitbox.x, self.hitbox.y)).norm() > hearing_radius { return; }
+
+        self.last_known_pos = pos;
+        self.awareness = self.start_searching();
+    }
+
+    fn update_attack(&mut self, dt: f32, fabien_hitbox: Rect) {
+        self.attack = match self.attack {
+            AttackState::Approaching => {
+                let dist = Vector2::new(
+                    (fabien_hitbox.x + fabien_hitbox.w / 2.0) - (self.hitbox.x + self.hitbox.w / 2.0),
+                    (fabien_hitbox.y + fabien_hitbox.h / 2.0) - (self.hitbox.y + self.hitbox.h / 2.0)
+                );
+                if self.awareness == Awareness::Chasing && self.attack_cooldown <= 0.0
+                    && dist.norm() < ATTACK_RANGE
+                {
+                    // Turn towards Fabien once, he won't follow him during the wind-up
+                    self.face(Point2::new(self.hitbox.x + dist.x, self.hitbox.y + dist.y));
+                    AttackState::WindUp(0.0)
+                } else { AttackState::Approaching }
+            },
+            AttackState::WindUp(time) => {
+                if time + dt > WIND_UP_TIME {
+                    self.strike_landed = false;
+                    AttackState::Strike(0.0)
+                } else { AttackState::WindUp(time + dt) }
+            },
+            AttackState::Strike(time) => {
+                if time + dt > STRIKE_TIME { AttackState::Recovery(0.0) }
+                else { AttackState::Strike(time + dt) }
+            },
+            AttackState::Recovery(time) => {
+                if time + dt > RECOVERY_TIME {
+                    self.attack_cooldown = ATTACK_COOLDOWN;
+                    AttackState::Approaching
+                } else { AttackState::Recovery(time + dt) }
             }
+        };
+    }
+
+    // Follows the way around what's in between them, or goes straight at
+    // Fabien once he's close (or if there's no way)
+    fn chase(&mut self, ctx: &mut Context, target: Point2<f32>, map: &Map) {
+        self.repath_time -= ggez::timer::delta(ctx).as_secs_f32();
+        if self.repath_time <= 0.0 {
+            self.repath_time = REPATH_TIME;
+            self.path = map.find_path(Point2::new(self.hitbox.x, self.hitbox.y), target, (self.hitbox.w, self.hitbox.h))
+                .map(|path| path.into_iter().rev().collect())
+                .unwrap_or_default();
         }
 
-        for p in self.particles.iter_mut() { p.update(ctx); }
-        self.particles.retain(|p| !p.is_dead());
+        let next = self.path.last().copied().unwrap_or(target);
+        if !self.go_to(ctx, next, self.speed, map) {
+            // Something got in the way, look for another way next frame
+            self.repath_time = 0.0;
+        } else if self.hitbox.x == next.x && self.hitbox.y == next.y {
+            self.path.pop();
+        }
+    }
 
-        Ok(())
+    // Move towards the target, and snap on it when it's less than a step away
+    // Returns false if he's stuck
+    fn go_to(&mut self, ctx: &mut Context, target: Point2<f32>, speed: f32, map: &Map) -> bool {
+        let next_move = speed * ggez::timer::delta(ctx).as_secs_f32();
+
+        let moved = self.move_towards(ctx, (target.x, target.y), speed, map);
+
+        if self.hitbox.x < target.x + next_move && self.hitbox.x > target.x - next_move {
+            self.hitbox.x = target.x;
+        }
+        if self.hitbox.y < target.y + next_move && self.hitbox.y > target.y - next_move {
+            self.hitbox.y = target.y;
+        }
+
+        moved
     }
 
-    fn move_towards(&mut self, ctx: &mut Context, target: (f32, f32)) {
+    // Goes around what's in the way: if he can't go straight there he slides
+    // along it on one axis, or steps aside. Returns false if nothing worked.
+    fn move_towards(&mut self, ctx: &mut Context, target: (f32, f32), speed: f32, map: &Map) -> bool {
         let dt = ggez::timer::delta(ctx).as_secs_f32();
+        let speed = speed * map.speed_factor(self.hitbox) * (1.0 + NIGHT_SPEED_BONUS * self.darkness);
 
         let mut dir = Vector2::new(0.0, 0.0);
         if target.0 < self.hitbox.x {
@@ -157,11 +446,35 @@ impl Bertrand {
             self.facing = "front".to_string();
         }
 
-        let vel_x = dir.x * self.speed * dt;
-        let vel_y = dir.y * self.speed * dt;
+        let vel_x = dir.x * speed * dt;
+        let vel_y = dir.y * speed * dt;
+        let step = speed * dt;
+
+        let mut moves = vec![(vel_x, vel_y), (vel_x, 0.0), (0.0, vel_y)];
+        if vel_y == 0.0 { moves.extend([(0.0, step), (0.0, -step)]); }
+        if vel_x == 0.0 { moves.extend([(step, 0.0), (-step, 0.0)]); }
+
+        // If he's already stuck in something, let him get out of it
+        let stuck = map.blocks_walking(self.hitbox);
+        for (x, y) in moves {
+            if x == 0.0 && y == 0.0 { continue; }
+            let moved = Rect::new(self.hitbox.x + x, self.hitbox.y + y, self.hitbox.w, self.hitbox.h);
+            if stuck || !map.blocks_walking(moved) {
+                self.hitbox = moved;
+                return true;
+            }
+        }
 
-        self.hitbox.x += vel_x;
-        self.hitbox.y += vel_y;
+        vel_x == 0.0 && vel_y == 0.0
+    }
+
+    // Turn towards something without moving
+    fn face(&mut self, target: Point2<f32>) {
+        let dir = target - Point2::new(self.hitbox.x, self.hitbox.y);
+        let facing = if dir.x.abs() > dir.y.abs() {
+            if dir.x < 0.0 { "left" } else { "right" }
+        } else if dir.y < 0.0 { "back" } else { "front" };
+        self.facing = facing.to_string();
     }
 
     fn spawn_leaf_particles(&mut self, ctx: &mut Context) -> GameResult {
@@ -194,15 +507,49 @@ impl Bertrand {
         self.hitbox
     }
 
-    pub fn is_dead(&self) -> bool {
-        self.swinging.1 > 0.5
+    pub fn get_kind(&self) -> BertrandKind {
+        self.kind
+    }
+
+    pub fn get_wave(&self) -> u32 {
+        self.wave
+    }
+
+    // The zone in front of Bertrand that hurts, only while he's striking
+    // and only until the blow landed once
+    pub fn get_attack_hitbox(&self) -> Option<Rect> {
+        if let AttackState::Strike(_) = self.attack {
+            if self.strike_landed || self.is_dying() { return None; }
+
+            let h = self.hitbox;
+            let attack_hitbox = match &self.facing[..] {
+                "left" => Rect::new(h.x - ATTACK_REACH, h.y + 4.0, ATTACK_REACH, 10.0),
+                "right" => Rect::new(h.x + h.w, h.y + 4.0, ATTACK_REACH, 10.0),
+                "back" => Rect::new(h.x - 1.0, h.y - ATTACK_REACH + 4.0, h.w + 2.0, ATTACK_REACH),
+                "front" => Rect::new(h.x - 1.0, h.y + h.h - 4.0, h.w + 2.0, ATTACK_REACH),
+                _ => unreachable!()
+            };
+            Some(attack_hitbox)
+        } else { None }
+    }
+
+    pub fn land_strike(&mut self) {
+        self.strike_landed = true;
     }
 
-    pub fn is_swinging(&self) -> bool {
-        self.swinging.0
+    pub fn kill(&mut self) {
+        self.dying = Some(0.0);
     }
 
-    pub fn swing(&mut self) {
-        self.swinging.0 = true;
+    pub fn is_dying(&self) -> bool {
+        self.dying.is_some()
+    }
+
+    // Dead and gone, the body faded out
+    pub fn is_dead(&self) -> bool {
+        match self.dying {
+            Some(time) => time > FALL_TIME + CORPSE_TIME,
+            None => false
+        }
     }
 }
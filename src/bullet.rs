@@ -11,7 +11,9 @@ pub struct Bullet {
     hitbox: Rect,
     pos: (f32, f32),
     nb_pierce: i8,
-    life: f32
+    life: f32,
+    // Some if it blows up when hitting a Bertrand
+    explosion_radius: Option<f32>
 }
 
 impl Bullet {
@@ -38,7 +40,8 @@ impl Bullet {
             hitbox: hitbox,
             pos: (0.0, 0.0),
             nb_pierce: nb_pierce, 
-            life: life
+            life: life,
+            explosion_radius: None
         };
 
         Ok(bullet)
@@ -81,5 +84,13 @@ impl Bullet {
     pub fn get_nb_pierce(&self) -> i8 {
         self.nb_pierce
     }
+
+    pub fn set_explosive(&mut self, radius: f32) {
+        self.explosion_radius = Some(radius);
+    }
+
+    pub fn get_explosion_radius(&self) -> Option<f32> {
+        self.explosion_radius
+    }
 }
 
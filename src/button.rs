@@ -100,6 +100,12 @@ impl Button {
         )); 
     }
 
+    pub fn set_text(&mut self, ctx: &mut Context, text: String) {
+        self.text.set_string(text);
+        self.text.change_color(if self.hovered { self.colors.1 } else { self.colors.0 });
+        self.set_pos(ctx, self.hitbox.x, self.hitbox.y);
+    }
+
     pub fn set_text_scale(&mut self, scale: f32) {
         self.text.change_scale(scale);
     }
@@ -0,0 +1,169 @@
+use ggez::{
+    Context,
+    graphics::Rect,
+    nalgebra::{ Point2, Vector2 },
+    input::mouse
+};
+use rand::Rng;
+
+// How many screen pixels a world unit takes, the editor starts at the same one
+pub const DEFAULT_ZOOM: f32 = 4.5;
+// What the mouse wheel goes through in game, from far to close
+const ZOOM_LEVELS: [f32; 4] = [3.0, 3.75, DEFAULT_ZOOM, 5.5];
+// How fast the camera catches up with Fabien (and the zoom with the wheel), higher is snappier
+const FOLLOW_SPEED: f32 = 6.0;
+const ZOOM_SPEED: f32 = 8.0;
+// How far ahead of Fabien it looks when the mouse is at the edge of the screen
+const LOOK_AHEAD: f32 = 25.0;
+// How far the view moves at full trauma, and how much trauma goes away each second
+const MAX_SHAKE: f32 = 4.0;
+const TRAUMA_DECAY: f32 = 1.5;
+
+// What's on screen during a game. It follows Fabien, looking a bit towards
+// where the mouse is, and shakes when things go wrong.
+pub struct Camera {
+    // The middle of the view, without the shaking
+    center: Point2<f32>,
+    shake: Vector2<f32>,
+    trauma: f32,
+    zoom: f32,
+    zoom_level: usize,
+    screen_size: (f32, f32),
+    map_size: (f32, f32)
+}
+
+impl Camera {
+    pub fn new(screen_size: (f32, f32), map_size: (f32, f32)) -> Camera {
+        Camera {
+            center: Point2::new(map_size.0 / 2.0, map_size.1 / 2.0),
+            shake: Vector2::new(0.0, 0.0),
+            trauma: 0.0,
+            zoom: DEFAULT_ZOOM,
+            zoom_level: 2,
+            screen_size: screen_size,
+            map_size: map_size
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, target: Point2<f32>) {
+        let dt = ggez::timer::delta(ctx).as_secs_f32();
+
+        // Where the mouse is compared to the middle of the window, from -1 to 1
+        let mouse = mouse::position(ctx);
+        let aim = Vector2::new(
+            (mouse.x / self.screen_size.0 * 2.0 - 1.0).clamp(-1.0, 1.0),
+            (mouse.y / self.screen_size.1 * 2.0 - 1.0).clamp(-1.0, 1.0)
+        );
+        let target = target + aim * LOOK_AHEAD;
+        self.center += (target - self.center) * (1.0 - (-FOLLOW_SPEED * dt).exp());
+
+        let zoom = ZOOM_LEVELS[self.zoom_level];
+        self.zoom += (zoom - self.zoom) * (1.0 - (-ZOOM_SPEED * dt).exp());
+
+        // The shaking goes with the square of the trauma, so small hits stay small
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        let shake = self.trauma * self.trauma * MAX_SHAKE;
+        let mut rng = rand::thread_rng();
+        self.shake = Vector2::new(rng.gen_range(-1.0..=1.0) * shake, rng.gen_range(-1.0..=1.0) * shake);
+    }
+
+    // Straight there, when a game starts
+    pub fn snap_to(&mut self, target: Point2<f32>) {
+        self.center = target;
+        self.zoom = ZOOM_LEVELS[self.zoom_level];
+        self.trauma = 0.0;
+        self.shake = Vector2::new(0.0, 0.0);
+    }
+
+    // From 0 to 1, it adds up and goes away by itself
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    // Closer with the wheel going up, further away going down
+    pub fn zoom_by(&mut self, steps: f32) {
+        if steps > 0.0 {
+            self.zoom_level = (self.zoom_level + 1).min(ZOOM_LEVELS.len() - 1);
+        } else if steps < 0.0 {
+            self.zoom_level = self.zoom_level.saturating_sub(1);
+        }
+    }
+
+    pub fn set_map_size(&mut self, map_size: (f32, f32)) {
+        self.map_size = map_size;
+    }
+
+    pub fn resize(&mut self, screen_size: (f32, f32)) {
+        self.screen_size = screen_size;
+    }
+
+    // What the screen shows, in world units
+    pub fn get_view(&self) -> Rect {
+        let (w, h) = (self.screen_size.0 / self.zoom, self.screen_size.1 / self.zoom);
+        let mut view = Rect::new(self.center.x - w / 2.0, self.center.y - h / 2.0, w, h);
+
+        // Keep it on the map, or the map in the middle of it if it's smaller than the view
+        if self.map_size.0 <= w { view.x = (self.map_size.0 - w) / 2.0; }
+        else { view.x = view.x.clamp(0.0, self.map_size.0 - w); }
+        if self.map_size.1 <= h { view.y = (self.map_size.1 - h) / 2.0; }
+        else { view.y = view.y.clamp(0.0, self.map_size.1 - h); }
+
+        view.x += self.shake.x;
+        view.y += self.shake.y;
+        view
+    }
+
+    // Where a point of the window (the mouse) is on the map
+    pub fn screen_to_world(&self, x: f32, y: f32) -> Point2<f32> {
+        let view = self.get_view();
+        Point2::new(view.x + x / self.screen_size.0 * view.w, view.y + y / self.screen_size.1 * view.h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_view_stays_on_the_map() {
+        let mut camera = Camera::new((900.0, 450.0), (1000.0, 1000.0));
+        let (w, h) = (900.0 / DEFAULT_ZOOM, 450.0 / DEFAULT_ZOOM);
+
+        camera.snap_to(Point2::new(500.0, 500.0));
+        let view = camera.get_view();
+        assert_eq!((view.x, view.y, view.w, view.h), (500.0 - w / 2.0, 500.0 - h / 2.0, w, h));
+
+        camera.snap_to(Point2::new(0.0, 0.0));
+        let view = camera.get_view();
+        assert_eq!((view.x, view.y), (0.0, 0.0));
+
+        camera.snap_to(Point2::new(1000.0, 1000.0));
+        let view = camera.get_view();
+        assert_eq!((view.x, view.y), (1000.0 - w, 1000.0 - h));
+    }
+
+    #[test]
+    fn small_maps_are_in_the_middle() {
+        // As wide as the view is, but not as tall
+        let mut camera = Camera::new((900.0, 450.0), (150.0, 500.0));
+        let (w, h) = (900.0 / DEFAULT_ZOOM, 450.0 / DEFAULT_ZOOM);
+
+        camera.snap_to(Point2::new(0.0, 0.0));
+        let view = camera.get_view();
+        assert_eq!((view.x, view.y), ((150.0 - w) / 2.0, 0.0));
+
+        camera.snap_to(Point2::new(75.0, 500.0));
+        let view = camera.get_view();
+        assert_eq!((view.x, view.y), ((150.0 - w) / 2.0, 500.0 - h));
+    }
+
+    #[test]
+    fn the_mouse_points_at_the_view() {
+        let mut camera = Camera::new((900.0, 450.0), (1000.0, 1000.0));
+        camera.snap_to(Point2::new(500.0, 500.0));
+        let view = camera.get_view();
+
+        assert_eq!(camera.screen_to_world(0.0, 0.0), Point2::new(view.x, view.y));
+        assert_eq!(camera.screen_to_world(900.0, 450.0), Point2::new(view.x + view.w, view.y + view.h));
+    }
+}
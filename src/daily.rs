@@ -0,0 +1,92 @@
+use std::env;
+use std::time::{ SystemTime, UNIX_EPOCH };
+use crate::storage::{ Storage, DailyRecord };
+
+// Everyone playing the same day gets the same map and the same rules, since the
+// seed only depends on the (UTC) date. The Bertrands and the powerups come from
+// the same seed too, but they're rolled every frame and the loot depends on what
+// the player does, so they don't come out the same for everyone.
+pub struct DailyChallenge {
+    date: String,
+    seed: u64,
+    // Only the first attempt of the day counts, the other ones are for practice
+    scored: bool
+}
+
+impl DailyChallenge {
+    pub fn today(storage: &Storage) -> DailyChallenge {
+        let (year, month, day) = utc_date();
+        let date = format!("{:04}-{:02}-{:02}", year, month, day);
+        let scored = storage.daily.get(&date).and_then(|record| record.scored).is_none();
+
+        DailyChallenge {
+            seed: seed_from_date(&date),
+            date: date,
+            scored: scored
+        }
+    }
+
+    // Write down the attempt as soon as it starts, so that quitting before
+    // the end doesn't give another go at the one that counts
+    pub fn start(&self, storage: &mut Storage) {
+        let record = storage.daily.entry(self.date.clone()).or_default();
+        record.attempts += 1;
+        if self.scored { record.scored = Some(0); }
+    }
+
+    // Write down how it went, returns the record of the day
+    pub fn record(&self, storage: &mut Storage, score: u32, wave: u32) -> DailyRecord {
+        let record = storage.daily.entry(self.date.clone()).or_default();
+        if self.scored { record.scored = Some(score); }
+        if score >= record.best_score {
+            record.best_score = score;
+            record.best_wave = wave;
+        }
+
+        *record
+    }
+
+    // The daily leaderboard is optional, its id is in the .env like the other ones
+    pub fn table_id() -> Option<String> {
+        env::var("TABLE_ID_DAILY").ok()
+    }
+
+    pub fn get_date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn is_scored(&self) -> bool {
+        self.scored
+    }
+}
+
+// FNV-1a, it only needs to be the same on every computer
+fn seed_from_date(date: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in date.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// Today's date in UTC, without pulling a whole date crate for it
+// (days to civil date, from Howard Hinnant's algorithms)
+fn utc_date() -> (i64, u32, u32) {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
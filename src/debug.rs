@@ -0,0 +1,95 @@
+use ggez::{
+    graphics, Context, GameResult,
+    graphics::{ Color, Rect },
+    nalgebra::{ Point2, Vector2 }
+};
+
+use crate::text::Text;
+use crate::ui::{ Ui, Anchor };
+
+// The text is drawn big and scaled down, like the rest of the HUD
+const TEXT_SCALE: f32 = 0.16;
+const PADDING: f32 = 6.0;
+
+// What a hitbox belongs to, each has its own color
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HitboxKind {
+    Fabien,
+    Bertrand,
+    // Where a Bertrand's blow lands
+    Attack,
+    Bullet,
+    Obstacle,
+    Powerup
+}
+
+impl HitboxKind {
+    fn color(&self) -> Color {
+        match self {
+            HitboxKind::Fabien => Color::from_rgb(66, 135, 245),
+            HitboxKind::Bertrand => Color::from_rgb(230, 40, 40),
+            HitboxKind::Attack => Color::from_rgb(255, 140, 20),
+            HitboxKind::Bullet => Color::from_rgb(255, 230, 40),
+            HitboxKind::Obstacle => Color::from_rgb(40, 220, 90),
+            HitboxKind::Powerup => Color::from_rgb(230, 60, 230)
+        }
+    }
+}
+
+// What's going on under the hood (F3), to balance things and hunt bugs
+pub struct DebugOverlay {
+    shown: bool
+}
+
+impl Default for DebugOverlay {
+    fn default() -> DebugOverlay {
+        DebugOverlay::new()
+    }
+}
+
+impl DebugOverlay {
+    pub fn new() -> DebugOverlay {
+        DebugOverlay {
+            shown: false
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    // Over the world, in world units
+    pub fn draw_hitboxes(&self, ctx: &mut Context, hitboxes: &[(Rect, HitboxKind)]) -> GameResult {
+        if !self.shown || hitboxes.is_empty() { return Ok(()); }
+
+        let mut mesh = graphics::MeshBuilder::new();
+        for (hitbox, kind) in hitboxes.iter() {
+            mesh.rectangle(graphics::DrawMode::stroke(0.5), *hitbox, kind.color());
+        }
+        let mesh = mesh.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+    }
+
+    // On the UI canvas, on the left side of the screen
+    pub fn draw_stats(&self, ctx: &mut Context, ui: &Ui, lines: &[String]) -> GameResult {
+        if !self.shown { return Ok(()); }
+
+        let text = Text::new(ctx, lines.join("\n"), "/Fonts/arial_narrow_7.ttf".to_string(),
+                            100.0, graphics::WHITE)?;
+        let size = (text.width(ctx) * TEXT_SCALE, text.height(ctx) * TEXT_SCALE);
+        let pos = ui.place(Anchor::Left, (size.0 + PADDING * 2.0, size.1 + PADDING * 2.0), (3.0, 0.0));
+
+        let background = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(),
+            Rect::new(pos.x, pos.y, size.0 + PADDING * 2.0, size.1 + PADDING * 2.0), Color::new(0.0, 0.0, 0.0, 0.6))?;
+        graphics::draw(ctx, &background, graphics::DrawParam::default())?;
+
+        graphics::queue_text(ctx, text.get_ggez_text(), Point2::new(0.0, 0.0), None);
+        graphics::draw_queued_text(ctx, graphics::DrawParam::new()
+            .scale(Vector2::new(TEXT_SCALE, TEXT_SCALE))
+            .dest(Point2::new(pos.x + PADDING, pos.y + PADDING)), None, graphics::FilterMode::Nearest)
+    }
+}
@@ -0,0 +1,88 @@
+use ggez::{
+    graphics, Context, GameResult,
+    graphics::spritebatch,
+    nalgebra::{ Point2, Vector2 }
+};
+use rand::Rng;
+use std::collections::VecDeque;
+
+// How long before the end of its life a decal starts fading out
+const FADE_TIME: f32 = 5.0;
+
+struct Decal {
+    pos: Point2<f32>,
+    rotation: f32,
+    scale: f32,
+    age: f32
+}
+
+// Stuff that stays on the ground (blood splats for now). They're all drawn
+// at once with a sprite batch so it doesn't matter much if there are a lot of them.
+pub struct Decals {
+    batch: spritebatch::SpriteBatch,
+    decals: VecDeque<Decal>,
+    max_decals: usize,
+    lifetime: f32
+}
+
+impl Decals {
+    pub fn new(ctx: &mut Context, max_decals: usize, lifetime: f32) -> GameResult<Decals> {
+        let image = graphics::Image::new(ctx, "/blood.png")?;
+
+        let decals = Decals {
+            batch: spritebatch::SpriteBatch::new(image),
+            decals: VecDeque::new(),
+            max_decals: max_decals,
+            lifetime: lifetime
+        };
+
+        Ok(decals)
+    }
+
+    pub fn add(&mut self, pos: Point2<f32>) {
+        let mut rng = rand::thread_rng();
+
+        // Too many of them, the oldest one goes away
+        if self.decals.len() >= self.max_decals {
+            self.decals.pop_front();
+        }
+
+        self.decals.push_back(Decal {
+            pos: pos,
+            rotation: rng.gen::<f32>() * 2.0 * std::f32::consts::PI,
+            scale: rng.gen::<f32>() * 0.5 + 0.75,
+            age: 0.0
+        });
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) {
+        let dt = ggez::timer::delta(ctx).as_secs_f32();
+
+        for decal in self.decals.iter_mut() { decal.age += dt; }
+        let lifetime = self.lifetime;
+        self.decals.retain(|d| d.age < lifetime);
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        self.batch.clear();
+        for decal in self.decals.iter() {
+            let time_left = self.lifetime - decal.age;
+            let alpha = if time_left < FADE_TIME { time_left / FADE_TIME } else { 1.0 };
+
+            let param = graphics::DrawParam::new()
+                .dest(decal.pos)
+                .offset(Point2::new(0.5, 0.5))
+                .rotation(decal.rotation)
+                .scale(Vector2::new(decal.scale, decal.scale))
+                .color(graphics::Color::new(1.0, 1.0, 1.0, alpha));
+            self.batch.add(param);
+        }
+        graphics::draw(ctx, &self.batch, graphics::DrawParam::new())?;
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.decals.clear();
+    }
+}
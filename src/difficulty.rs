@@ -0,0 +1,97 @@
+use std::env;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Nightmare
+}
+
+impl Difficulty {
+    // The one after this one, to cycle through them in the settings
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Nightmare,
+            Difficulty::Nightmare => Difficulty::Easy
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Facile",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Difficile",
+            Difficulty::Nightmare => "Cauchemar"
+        }
+    }
+
+    pub fn bertrand_speed_factor(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.15,
+            Difficulty::Nightmare => 1.3
+        }
+    }
+
+    // Multiplies the spawn rate of the waves, so lower means more Bertrands
+    pub fn spawn_rate_factor(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.4,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.75,
+            Difficulty::Nightmare => 0.55
+        }
+    }
+
+    // Same thing for the powerups, higher means less of them
+    pub fn powerup_rate_factor(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Nightmare => 1.6
+        }
+    }
+
+    pub fn max_health(&self) -> u8 {
+        match self {
+            Difficulty::Easy => 14,
+            Difficulty::Normal => 10,
+            Difficulty::Hard => 7,
+            Difficulty::Nightmare => 4
+        }
+    }
+
+    pub fn starting_ammos(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 60,
+            Difficulty::Normal => 44,
+            Difficulty::Hard => 34,
+            Difficulty::Nightmare => 24
+        }
+    }
+
+    // How the difficulty is written in the .env variables
+    pub fn env_key(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+            Difficulty::Nightmare => "NIGHTMARE"
+        }
+    }
+
+    // Each difficulty has its own GameJolt leaderboard, the ids are in the .env
+    // like the other GameJolt infos (the normal one is the original table)
+    pub fn table_id(&self) -> Option<String> {
+        match env::var(format!("TABLE_ID_{}", self.env_key())) {
+            Ok(id) => Some(id),
+            Err(_) if *self == Difficulty::Normal => Some(String::from("594910")),
+            Err(_) => None
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+// The pressure multiplies how fast Bertrands come, it stays between those
+const MIN_PRESSURE: f32 = 0.4;
+const MAX_PRESSURE: f32 = 2.0;
+// How far back (in seconds) the director remembers hits and kills
+const MEMORY: f64 = 20.0;
+// How long the director goes easy on Fabien after he almost died
+const RELIEF_TIME: f64 = 15.0;
+// How often the director writes down what it thinks
+const LOG_INTERVAL: f64 = 5.0;
+
+// What the director wants to do about the next powerup
+pub struct PowerupPlan {
+    pub near_fabien: bool,
+    // Id of the powerup to put there, None means any powerup will do
+    pub powerup: Option<&'static str>
+}
+
+// The director watches how Fabien is doing and decides how many Bertrands
+// to send and where to put the powerups, instead of leaving it all to chance.
+// It eases off when Fabien almost died and pushes harder when he's cruising.
+pub struct Director {
+    pressure: f32,
+    hits: VecDeque<f64>,
+    kills: VecDeque<f64>,
+    relief_until: f64,
+    near_death: bool,
+    spawn_credit: f32,
+    powerup_credit: f32,
+    last_log: f64,
+    struggling: (bool, bool)
+}
+
+impl Default for Director {
+    fn default() -> Director {
+        Director::new()
+    }
+}
+
+impl Director {
+    pub fn new() -> Director {
+        Director {
+            pressure: 1.0,
+            hits: VecDeque::new(),
+            kills: VecDeque::new(),
+            relief_until: 0.0,
+            near_death: false,
+            spawn_credit: 0.0,
+            powerup_credit: 0.0,
+            last_log: 0.0,
+            struggling: (false, false)
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Director::new();
+    }
+
+    pub fn get_pressure(&self) -> f32 {
+        self.pressure
+    }
+
+    pub fn record_hit(&mut self, time: f64) {
+        self.hits.push_back(time);
+    }
+
+    pub fn record_kills(&mut self, time: f64, nb_kills: u32) {
+        for _ in 0..nb_kills { self.kills.push_back(time); }
+    }
+
+    pub fn update(&mut self, time: f64, dt: f32, health: u8, max_health: u8, ammos: u32) {
+        while self.hits.front().is_some_and(|t| time - t > MEMORY) { self.hits.pop_front(); }
+        while self.kills.front().is_some_and(|t| time - t > MEMORY) { self.kills.pop_front(); }
+
+        let health_ratio = health as f32 / max_health as f32;
+        let kills_per_minute = self.kills.len() as f32 * (60.0 / MEMORY as f32);
+        self.struggling = (health_ratio <= 0.4, ammos < 10);
+
+        // Fabien almost died, give him some time to breathe. Not in Hardcore
+        // where he always has a single point of health, he'd never be out of it.
+        let near_death = max_health > 1 && health <= (max_health / 5).max(1);
+        if near_death && !self.near_death {
+            self.relief_until = time + RELIEF_TIME;
+            self.log(time, &format!("near death ({}/{}), easing off for {}s", health, max_health, RELIEF_TIME));
+        }
+        self.near_death = near_death;
+
+        let target = if time < self.relief_until {
+            MIN_PRESSURE
+        } else {
+            let mut target = 1.0 + (health_ratio - 0.5) * 0.8
+                + (kills_per_minute / 20.0).min(1.0) * 0.6
+                - self.hits.len() as f32 * 0.15;
+            if self.struggling.1 { target -= 0.3; }
+            target.clamp(MIN_PRESSURE, MAX_PRESSURE)
+        };
+
+        // Don't change the pressure too suddenly
+        self.pressure += (target - self.pressure) * (dt * 0.5).min(1.0);
+
+        if time - self.last_log > LOG_INTERVAL {
+            self.last_log = time;
+            self.log(time, &format!("pressure {:.2} (target {:.2}), health {}/{}, ammos {}, hits {}, kills/min {:.1}",
+                self.pressure, target, health, max_health, ammos, self.hits.len(), kills_per_minute));
+        }
+    }
+
+    // Replaces the random check done for each frame. The average time between
+    // two spawns with that check is about sqrt(pi * rate / (2 * fps)), so that's
+    // what the director uses at a pressure of 1.
+    pub fn should_spawn_bertrand(&mut self, dt: f32, fps: f64, spawn_rate: f32) -> bool {
+        self.spawn_credit += dt * self.pressure / average_interval(spawn_rate, fps);
+        if self.spawn_credit >= 1.0 {
+            self.spawn_credit -= 1.0;
+            true
+        } else { false }
+    }
+
+    // More powerups when the pressure is low, and the right ones close to
+    // Fabien if he's running out of something
+    pub fn plan_powerup(&mut self, time: f64, dt: f32, fps: f64, powerup_rate: f32) -> Option<PowerupPlan> {
+        let factor = (MAX_PRESSURE + MIN_PRESSURE - self.pressure).max(MIN_PRESSURE);
+        self.powerup_credit += dt * factor / average_interval(powerup_rate, fps);
+        if self.powerup_credit < 1.0 { return None; }
+        self.powerup_credit -= 1.0;
+
+        let plan = match self.struggling {
+            (true, _) => PowerupPlan { near_fabien: true, powerup: Some("sandwich") },
+            (_, true) => PowerupPlan { near_fabien: true, powerup: Some("ammo_restock") },
+            _ => PowerupPlan { near_fabien: false, powerup: None }
+        };
+        if plan.near_fabien {
+            self.log(time, &format!("placing a {} near Fabien",
+                if self.struggling.0 { "heal" } else { "ammo restock" }));
+        }
+
+        Some(plan)
+    }
+
+    fn log(&self, time: f64, message: &str) {
+        println!("[director {:>7.1}s] {}", time, message);
+    }
+}
+
+fn average_interval(rate: f32, fps: f64) -> f32 {
+    (std::f32::consts::PI * rate / (2.0 * fps.max(1.0) as f32)).sqrt().max(0.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relief_comes_after_a_close_call() {
+        let mut director = Director::new();
+        director.update(1.0, 0.1, 1, 1, 30);
+        assert_eq!(director.relief_until, 0.0);
+
+        director.update(2.0, 0.1, 5, 5, 30);
+        assert_eq!(director.relief_until, 0.0);
+        director.update(3.0, 0.1, 1, 5, 30);
+        assert_eq!(director.relief_until, 3.0 + RELIEF_TIME);
+    }
+}
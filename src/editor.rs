@@ -0,0 +1,502 @@
+use ggez::{
+    Context, GameResult, graphics, timer,
+    graphics::{ Color, Rect, spritebatch::SpriteBatch },
+    nalgebra::Point2,
+    input::{ mouse::MouseButton, keyboard::{ self, KeyCode, KeyMods } }
+};
+use rand::Rng;
+use crate::map::Obstacle;
+use crate::terrain::ObstacleKind;
+use crate::tiled::{ TiledMap, Object, SpawnKind };
+use crate::text::Text;
+use crate::camera::DEFAULT_ZOOM;
+
+// Where the editor keeps its map (in the user folder), it can then be picked
+// in the settings like the other maps
+pub const EDITOR_MAP_PATH: &str = "/maps/editeur.json";
+const TILE_SIZE: u32 = 16;
+// Size of a new map, in tiles
+const NEW_MAP_SIZE: (u32, u32) = (75, 75);
+const GRASS_SPRITES: [&str; 5] = ["/grass_0.png", "/grass_1.png", "/grass_2.png", "/grass_3.png", "/grass_4.png"];
+// What can be put on the map, what it is and how it's called in the editor
+const OBSTACLES: [(&str, ObstacleKind, &str); 6] = [
+    ("/tree_0.png", ObstacleKind::Tree, "Petit arbre"),
+    ("/tree_1.png", ObstacleKind::Tree, "Grand arbre"),
+    ("/rock.png", ObstacleKind::Rock, "Rocher"),
+    ("/bush.png", ObstacleKind::Bush, "Buisson"),
+    ("/water.png", ObstacleKind::Water, "Eau"),
+    ("/mud.png", ObstacleKind::Mud, "Boue")
+];
+// Things snap to a grid of that size when the grid is on
+const GRID_SIZE: f32 = 8.0;
+// In screen pixels per second
+const PAN_SPEED: f32 = 800.0;
+const ZOOM_RANGE: (f32, f32) = (1.0, 12.0);
+const SPAWN_RADIUS: f32 = 4.0;
+// How long the messages (saved, loaded...) stay on screen
+const MESSAGE_TIME: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tool {
+    // Index in OBSTACLES
+    Obstacle(usize),
+    Spawn(SpawnKind)
+}
+
+const TOOLS: [Tool; 9] = [Tool::Obstacle(0), Tool::Obstacle(1), Tool::Obstacle(2), Tool::Obstacle(3),
+    Tool::Obstacle(4), Tool::Obstacle(5), Tool::Spawn(SpawnKind::Fabien),
+    Tool::Spawn(SpawnKind::Bertrand), Tool::Spawn(SpawnKind::Powerup)];
+
+impl Tool {
+    fn name(&self) -> &'static str {
+        match self {
+            Tool::Obstacle(i) => OBSTACLES[*i].2,
+            Tool::Spawn(SpawnKind::Fabien) => "Départ de Fabien",
+            Tool::Spawn(SpawnKind::Bertrand) => "Arrivée de Bertrands",
+            Tool::Spawn(SpawnKind::Powerup) => "Emplacement de powerup"
+        }
+    }
+}
+
+fn spawn_color(kind: SpawnKind) -> Color {
+    match kind {
+        SpawnKind::Fabien => Color::from_rgb(66, 135, 245),
+        SpawnKind::Bertrand => Color::from_rgb(220, 50, 50),
+        SpawnKind::Powerup => Color::from_rgb(240, 200, 40)
+    }
+}
+
+// The map as the editor saves it. The obstacles are their index in OBSTACLES,
+// their top left corner and the size of their image.
+pub fn tiled_map_of(size: (u32, u32), grass: &[Option<usize>], obstacles: &[(usize, Point2<f32>, (f32, f32))],
+                    spawns: &[(SpawnKind, Point2<f32>)]) -> TiledMap {
+    let obstacle_images: Vec<&str> = OBSTACLES.iter().map(|(path, _, _)| *path).collect();
+    let mut tiled_map = TiledMap::new(size, TILE_SIZE, &[("grass", &GRASS_SPRITES), ("obstacles", &obstacle_images)]);
+
+    let grass_gid = tiled_map.first_gid("grass").unwrap_or(1);
+    let data = grass.iter().map(|grass| grass.map_or(0, |i| grass_gid + i as u32)).collect();
+    tiled_map.add_tile_layer("grass", data);
+
+    let obstacle_gid = tiled_map.first_gid("obstacles").unwrap_or(1);
+    let obstacles = obstacles.iter()
+        .map(|(kind, pos, size)| Object::tile(obstacle_gid + *kind as u32, OBSTACLES[*kind].1.name(), *pos, *size))
+        .collect();
+    tiled_map.add_object_layer("obstacles", obstacles);
+    tiled_map.add_object_layer("spawns", spawns.iter().map(|(kind, pos)| Object::spawn(*kind, *pos)).collect());
+
+    tiled_map
+}
+
+// What's being moved around with the mouse
+enum Dragged {
+    // Index in the obstacles, and where it was grabbed from its top left corner
+    Obstacle(usize, (f32, f32)),
+    Spawn(usize)
+}
+
+// Places, moves and deletes obstacles and spawn points on a map, and saves it
+// as a Tiled map so that the game can load it like any other hand-made map
+pub struct Editor {
+    screen_size: (f32, f32),
+    // In tiles
+    size: (u32, u32),
+    // Index in GRASS_SPRITES for each tile, if there's grass there
+    grass: Vec<Option<usize>>,
+    grass_batches: Vec<SpriteBatch>,
+    obstacle_images: Vec<graphics::Image>,
+    // With their index in OBSTACLES
+    obstacles: Vec<(usize, Obstacle)>,
+    spawns: Vec<(SpawnKind, Point2<f32>)>,
+    // Index in TOOLS
+    tool: usize,
+    // Top left corner of what's shown, in the world
+    camera: Point2<f32>,
+    zoom: f32,
+    // On the screen
+    mouse: Point2<f32>,
+    dragging: Option<Dragged>,
+    panning: bool,
+    snap: bool,
+    message: Option<(String, f32)>
+}
+
+impl Editor {
+    // Opens the map of the editor, or a new one if there's none yet
+    pub fn new(ctx: &mut Context, screen_size: (f32, f32)) -> GameResult<Editor> {
+        let mut grass_batches = vec![];
+        for path in GRASS_SPRITES.iter() {
+            grass_batches.push(SpriteBatch::new(graphics::Image::new(ctx, *path)?));
+        }
+        let mut obstacle_images = vec![];
+        for (path, _, _) in OBSTACLES.iter() {
+            obstacle_images.push(graphics::Image::new(ctx, *path)?);
+        }
+
+        let mut editor = Editor {
+            screen_size: screen_size,
+            size: NEW_MAP_SIZE,
+            grass: vec![],
+            grass_batches: grass_batches,
+            obstacle_images: obstacle_images,
+            obstacles: vec![],
+            spawns: vec![],
+            tool: 0,
+            camera: Point2::new(0.0, 0.0),
+            zoom: DEFAULT_ZOOM,
+            mouse: Point2::new(0.0, 0.0),
+            dragging: None,
+            panning: false,
+            snap: true,
+            message: None
+        };
+
+        if ggez::filesystem::exists(ctx, EDITOR_MAP_PATH) {
+            editor.load(ctx);
+        } else {
+            editor.new_map();
+        }
+
+        Ok(editor)
+    }
+
+    // Some grass here and there, and Fabien in the middle
+    fn new_map(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.size = NEW_MAP_SIZE;
+        self.grass = (0..self.size.0 * self.size.1)
+            .map(|_| if rng.gen::<f32>() < 0.3 { Some(rng.gen_range(0..GRASS_SPRITES.len())) } else { None })
+            .collect();
+        self.obstacles.clear();
+        self.spawns = vec![(SpawnKind::Fabien, self.map_center())];
+        self.dragging = None;
+
+        self.rebuild_grass();
+        self.center_camera();
+        self.show_message("Nouvelle carte".to_string());
+    }
+
+    // Anything the editor doesn't know (other tilesets, other objects) is left out
+    fn load(&mut self, ctx: &mut Context) {
+        let tiled_map = match TiledMap::load(ctx, EDITOR_MAP_PATH) {
+            Ok(map) => map,
+            Err(e) => {
+                self.show_message(format!("Impossible de charger {} : {}", EDITOR_MAP_PATH, e));
+                return;
+            }
+        };
+
+        let (width, height) = tiled_map.pixel_size();
+        self.size = ((width / TILE_SIZE as f32).ceil() as u32, (height / TILE_SIZE as f32).ceil() as u32);
+        self.grass = vec![None; (self.size.0 * self.size.1) as usize];
+        for (gid, pos) in tiled_map.tiles() {
+            let sprite = tiled_map.tile_image(gid).and_then(|image| GRASS_SPRITES.iter().position(|path| *path == image));
+            let (col, row) = ((pos.x / TILE_SIZE as f32) as u32, (pos.y / TILE_SIZE as f32) as u32);
+            if sprite.is_some() && col < self.size.0 && row < self.size.1 {
+                self.grass[(row * self.size.0 + col) as usize] = sprite;
+            }
+        }
+
+        self.obstacles.clear();
+        self.spawns.clear();
+        let mut skipped = 0;
+        for object in tiled_map.objects() {
+            if let Some(gid) = object.get_gid() {
+                let kind = tiled_map.tile_image(gid).and_then(|image| OBSTACLES.iter().position(|(path, _, _)| *path == image));
+                match kind {
+                    Some(kind) => self.obstacles.push((kind, self.new_obstacle(kind, object.top_left()))),
+                    None => skipped += 1
+                }
+            } else if let Some(kind) = object.get_spawn() {
+                self.spawns.push((kind, object.top_left()));
+            }
+        }
+        self.dragging = None;
+
+        self.rebuild_grass();
+        self.center_camera();
+        self.show_message(if skipped > 0 {
+            format!("Carte chargée ({} objets inconnus ignorés)", skipped)
+        } else {
+            "Carte chargée".to_string()
+        });
+    }
+
+    fn save(&mut self, ctx: &mut Context) {
+        let obstacles: Vec<(usize, Point2<f32>, (f32, f32))> = self.obstacles.iter().map(|(kind, obstacle)| {
+            let image = &self.obstacle_images[*kind];
+            (*kind, obstacle.get_position(), (image.width() as f32, image.height() as f32))
+        }).collect();
+        let tiled_map = tiled_map_of(self.size, &self.grass, &obstacles, &self.spawns);
+
+        let message = match tiled_map.save(ctx, EDITOR_MAP_PATH) {
+            Ok(()) if !self.spawns.iter().any(|(kind, _)| *kind == SpawnKind::Fabien) =>
+                format!("Sauvegardée dans {} (sans départ, Fabien partira du centre)", EDITOR_MAP_PATH),
+            Ok(()) => format!("Sauvegardée dans {}", EDITOR_MAP_PATH),
+            Err(e) => format!("Impossible de sauvegarder : {}", e)
+        };
+        self.show_message(message);
+    }
+
+    fn rebuild_grass(&mut self) {
+        for batch in self.grass_batches.iter_mut() { batch.clear(); }
+        for (i, grass) in self.grass.iter().enumerate() {
+            if let Some(sprite) = grass {
+                let (col, row) = (i as u32 % self.size.0, i as u32 / self.size.0);
+                self.grass_batches[*sprite].add(graphics::DrawParam::new()
+                    .dest(Point2::new((col * TILE_SIZE) as f32, (row * TILE_SIZE) as f32)));
+            }
+        }
+    }
+
+    fn show_message(&mut self, message: String) {
+        self.message = Some((message, MESSAGE_TIME));
+    }
+
+    fn map_center(&self) -> Point2<f32> {
+        Point2::new((self.size.0 * TILE_SIZE) as f32 / 2.0, (self.size.1 * TILE_SIZE) as f32 / 2.0)
+    }
+
+    fn center_camera(&mut self) {
+        let center = self.map_center();
+        let view = self.view();
+        self.camera = Point2::new(center.x - view.w / 2.0, center.y - view.h / 2.0);
+    }
+
+    // The part of the world that's on screen
+    fn view(&self) -> Rect {
+        Rect::new(self.camera.x, self.camera.y, self.screen_size.0 / self.zoom, self.screen_size.1 / self.zoom)
+    }
+
+    fn screen_to_world(&self, pos: Point2<f32>) -> Point2<f32> {
+        Point2::new(self.camera.x + pos.x / self.zoom, self.camera.y + pos.y / self.zoom)
+    }
+
+    fn snapped(&self, pos: Point2<f32>) -> Point2<f32> {
+        if self.snap {
+            Point2::new((pos.x / GRID_SIZE).round() * GRID_SIZE, (pos.y / GRID_SIZE).round() * GRID_SIZE)
+        } else { pos }
+    }
+
+    fn new_obstacle(&self, kind: usize, pos: Point2<f32>) -> Obstacle {
+        Obstacle::new(OBSTACLES[kind].1, self.obstacle_images[kind].clone(), pos)
+    }
+
+    // Where an obstacle goes so that the mouse is on its trunk, or in the
+    // middle of it for water and mud
+    fn obstacle_pos(&self, kind: usize, mouse: Point2<f32>) -> Point2<f32> {
+        let image = &self.obstacle_images[kind];
+        let below = if OBSTACLES[kind].1.is_flat() { 0.5 } else { 0.8 };
+        self.snapped(Point2::new(mouse.x - image.width() as f32 / 2.0, mouse.y - image.height() as f32 * below))
+    }
+
+    // Spawn points are small and drawn on top, so they're checked first
+    fn object_at(&self, pos: Point2<f32>) -> Option<Dragged> {
+        if let Some(i) = self.spawns.iter().rposition(|(_, spawn)|
+            (spawn.x - pos.x).powi(2) + (spawn.y - pos.y).powi(2) <= SPAWN_RADIUS.powi(2))
+        {
+            return Some(Dragged::Spawn(i));
+        }
+
+        self.obstacles.iter().rposition(|(_, obstacle)| obstacle.get_sight_box().contains(pos)).map(|i| {
+            let obstacle_pos = self.obstacles[i].1.get_position();
+            Dragged::Obstacle(i, (pos.x - obstacle_pos.x, pos.y - obstacle_pos.y))
+        })
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        // ZQSD like in the game, unless it's a shortcut
+        if !keyboard::is_mod_active(ctx, KeyMods::CTRL) {
+            let step = PAN_SPEED / self.zoom * dt;
+            if keyboard::is_key_pressed(ctx, KeyCode::Z) { self.camera.y -= step; }
+            if keyboard::is_key_pressed(ctx, KeyCode::S) { self.camera.y += step; }
+            if keyboard::is_key_pressed(ctx, KeyCode::Q) { self.camera.x -= step; }
+            if keyboard::is_key_pressed(ctx, KeyCode::D) { self.camera.x += step; }
+        }
+
+        if let Some((_, time_left)) = self.message.as_mut() {
+            *time_left -= dt;
+            if *time_left <= 0.0 { self.message = None; }
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+        let view = self.view();
+        let (width, height) = ((self.size.0 * TILE_SIZE) as f32, (self.size.1 * TILE_SIZE) as f32);
+        graphics::set_screen_coordinates(ctx, view)?;
+
+        // Outside of the map is dark, to see where it ends
+        let outside = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), view, Color::from_rgb(35, 35, 35))?;
+        graphics::draw(ctx, &outside, graphics::DrawParam::default())?;
+        let ground = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(),
+            Rect::new(0.0, 0.0, width, height), Color::from_rgb(104, 159, 56))?;
+        graphics::draw(ctx, &ground, graphics::DrawParam::default())?;
+        for batch in self.grass_batches.iter() {
+            graphics::draw(ctx, batch, graphics::DrawParam::default())?;
+        }
+
+        // Water and mud on the ground, then the lower ones in front, like in the game
+        let mut order: Vec<&Obstacle> = self.obstacles.iter().map(|(_, obstacle)| obstacle).collect();
+        order.sort_by(|a, b| {
+            let bottom = |obstacle: &Obstacle| (!obstacle.get_kind().is_flat(), obstacle.get_hitbox().y + obstacle.get_hitbox().h);
+            bottom(a).partial_cmp(&bottom(b)).unwrap()
+        });
+        for obstacle in order.iter() {
+            obstacle.draw(ctx, graphics::WHITE)?;
+        }
+
+        // What's solid (red) or only slows down (yellow), and the spawn points
+        let mut overlay = graphics::MeshBuilder::new();
+        overlay.rectangle(graphics::DrawMode::stroke(1.0), Rect::new(0.0, 0.0, width, height), graphics::WHITE);
+        for (_, obstacle) in self.obstacles.iter() {
+            let color = if obstacle.get_kind().blocks_walking() {
+                Color::new(1.0, 0.2, 0.2, 0.7)
+            } else { Color::new(1.0, 0.9, 0.2, 0.7) };
+            overlay.rectangle(graphics::DrawMode::stroke(0.5), obstacle.get_hitbox(), color);
+        }
+        for (kind, pos) in self.spawns.iter() {
+            overlay.circle(graphics::DrawMode::fill(), *pos, SPAWN_RADIUS, 0.1, spawn_color(*kind));
+            overlay.circle(graphics::DrawMode::stroke(0.5), *pos, SPAWN_RADIUS, 0.1, graphics::WHITE);
+        }
+
+        // What would be placed with a click
+        let mouse = self.screen_to_world(self.mouse);
+        if self.dragging.is_none() && !self.panning {
+            match TOOLS[self.tool] {
+                Tool::Obstacle(kind) => {
+                    graphics::draw(ctx, &self.obstacle_images[kind], graphics::DrawParam::new()
+                        .dest(self.obstacle_pos(kind, mouse))
+                        .color(Color::new(1.0, 1.0, 1.0, 0.5)))?;
+                },
+                Tool::Spawn(kind) => {
+                    let mut color = spawn_color(kind);
+                    color.a = 0.5;
+                    overlay.circle(graphics::DrawMode::fill(), self.snapped(mouse), SPAWN_RADIUS, 0.1, color);
+                }
+            }
+        }
+        let overlay = overlay.build(ctx)?;
+        graphics::draw(ctx, &overlay, graphics::DrawParam::default())?;
+
+        // Back to the screen for the texts
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, self.screen_size.0, self.screen_size.1))?;
+        let help = format!("Outil : {} (Tab ou 1 à {} pour changer)\n\
+            Clic gauche : placer / déplacer - Clic droit : supprimer - Molette : zoom - ZQSD ou clic molette : se déplacer\n\
+            G : grille ({}) - Ctrl+S : sauvegarder - Ctrl+O : recharger - Ctrl+N : nouvelle carte - Échap : menu",
+            TOOLS[self.tool].name(), TOOLS.len(), if self.snap { "activée" } else { "désactivée" });
+        let mut help_text = Text::new(ctx, help, "/Fonts/arial_narrow_7.ttf".to_string(), 28.0, graphics::WHITE)?;
+        help_text.set_pos(Point2::new(10.0, 10.0));
+        help_text.draw(ctx)?;
+
+        if let Some((message, time_left)) = &self.message {
+            let alpha = time_left.min(1.0);
+            let mut message_text = Text::new(ctx, message.clone(), "/Fonts/arial_narrow_7.ttf".to_string(),
+                36.0, Color::new(1.0, 1.0, 1.0, alpha))?;
+            message_text.set_pos(Point2::new(10.0, self.screen_size.1 - message_text.height(ctx) - 10.0));
+            message_text.draw(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    // 1 means going back to the menu
+    pub fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) -> u8 {
+        let ctrl = keymods.contains(KeyMods::CTRL);
+        match keycode {
+            KeyCode::Escape => return 1,
+            KeyCode::Tab => self.tool = (self.tool + 1) % TOOLS.len(),
+            KeyCode::Key1 => self.tool = 0,
+            KeyCode::Key2 => self.tool = 1,
+            KeyCode::Key3 => self.tool = 2,
+            KeyCode::Key4 => self.tool = 3,
+            KeyCode::Key5 => self.tool = 4,
+            KeyCode::Key6 => self.tool = 5,
+            KeyCode::Key7 => self.tool = 6,
+            KeyCode::Key8 => self.tool = 7,
+            KeyCode::Key9 => self.tool = 8,
+            KeyCode::G => self.snap = !self.snap,
+            KeyCode::S if ctrl => self.save(ctx),
+            KeyCode::O if ctrl => self.load(ctx),
+            KeyCode::N if ctrl => self.new_map(),
+            _ => {}
+        }
+
+        0
+    }
+
+    pub fn mouse_button_down_event(&mut self, button: MouseButton, x: f32, y: f32) {
+        self.mouse = Point2::new(x, y);
+        let pos = self.screen_to_world(self.mouse);
+
+        match button {
+            MouseButton::Left => {
+                // Grab what's under the mouse, or put something new there
+                if let Some(dragged) = self.object_at(pos) {
+                    self.dragging = Some(dragged);
+                    return;
+                }
+
+                match TOOLS[self.tool] {
+                    Tool::Obstacle(kind) => {
+                        let obstacle = self.new_obstacle(kind, self.obstacle_pos(kind, pos));
+                        self.obstacles.push((kind, obstacle));
+                    },
+                    Tool::Spawn(kind) => {
+                        // Fabien only starts from one place
+                        if kind == SpawnKind::Fabien {
+                            self.spawns.retain(|(other, _)| *other != SpawnKind::Fabien);
+                        }
+                        self.spawns.push((kind, self.snapped(pos)));
+                    }
+                }
+            },
+            MouseButton::Right => {
+                match self.object_at(pos) {
+                    Some(Dragged::Spawn(i)) => { self.spawns.remove(i); },
+                    Some(Dragged::Obstacle(i, _)) => { self.obstacles.remove(i); },
+                    None => {}
+                }
+            },
+            MouseButton::Middle => self.panning = true,
+            _ => {}
+        }
+    }
+
+    pub fn mouse_button_up_event(&mut self, button: MouseButton) {
+        match button {
+            MouseButton::Left => self.dragging = None,
+            MouseButton::Middle => self.panning = false,
+            _ => {}
+        }
+    }
+
+    pub fn mouse_motion_event(&mut self, x: f32, y: f32, dx: f32, dy: f32) {
+        self.mouse = Point2::new(x, y);
+        if self.panning {
+            self.camera.x -= dx / self.zoom;
+            self.camera.y -= dy / self.zoom;
+        }
+
+        let pos = self.screen_to_world(self.mouse);
+        match self.dragging {
+            Some(Dragged::Obstacle(i, offset)) => {
+                let new_pos = self.snapped(Point2::new(pos.x - offset.0, pos.y - offset.1));
+                self.obstacles[i].1.set_position(new_pos);
+            },
+            Some(Dragged::Spawn(i)) => self.spawns[i].1 = self.snapped(pos),
+            None => {}
+        }
+    }
+
+    // Zooms on what's under the mouse
+    pub fn mouse_wheel_event(&mut self, y: f32) {
+        let before = self.screen_to_world(self.mouse);
+        self.zoom = (self.zoom * 1.2_f32.powf(y)).clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+        self.camera = Point2::new(before.x - self.mouse.x / self.zoom, before.y - self.mouse.y / self.zoom);
+    }
+
+    pub fn resize_event(&mut self, width: f32, height: f32) {
+        self.screen_size = (width, height);
+    }
+}
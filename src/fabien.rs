@@ -12,7 +12,20 @@ use crate::bullet::Bullet;
 use crate::powerup::Powerups;
 use crate::text::Text;
 use crate::particle::Particle;
-use crate::map::Tree;
+use crate::map::Map;
+use crate::difficulty::Difficulty;
+use crate::game_mode::GameMode;
+use crate::ui::{ Ui, Anchor };
+
+// What happened when a Bertrand hit Fabien
+#[derive(PartialEq)]
+pub enum Hit {
+    Taken,
+    // The shield took it for him
+    Absorbed,
+    // He was still invincible from the last one
+    Ignored
+}
 
 // Fabien is the player
 pub struct Fabien {
@@ -23,42 +36,31 @@ pub struct Fabien {
     piercing_bullet_sprite: graphics::Image,
     facing: String,
     hitbox: Rect,
-    camera: Rect,
     shooting: (bool, f32),
     ammos: u32,
     starting_ammos: u32,
     score: u32,
+    coins: u32,
     health: u8,
     max_health: u8,
     animation_cycle: u8,
     animation_time: f32,
     speed: f32,
     starting_speed: f32,
-    active_powerup: Option<Powerups>,
-    powerup_sprite: Option<graphics::Image>,
+    // The powerups that last a while (or a few hits), with their icon
+    active_powerups: Vec<(Powerups, graphics::Image)>,
     movement_queue: VecDeque<Movement>,
     map_size: (f32, f32),
     shots: VecDeque<Bullet>,
     particles: Vec<Particle>,
+    noise: Option<Point2<f32>>,
     invicibility_frames: u32
 }
 
 impl Fabien {
-    pub fn new(ctx: &mut Context, trees: &Vec<Tree>, map_size: (f32, f32), screen_size: (f32, f32)) -> GameResult<Fabien> {
-        let mut hitbox = Rect::new(map_size.0 / 2.0, map_size.1 / 2.0, 8.0, 16.0);
-        let mut overlaps;
-        'main: loop {
-            overlaps = false;
-            for tree in trees.iter() {
-                if hitbox.overlaps(&tree.get_hitbox()) {
-                    hitbox.x -= 10.0;
-                    hitbox.y -= 10.0;
-                    overlaps = true;
-                    break;
-                }
-            } 
-            if !overlaps { break 'main; }
-        }
+    pub fn new(ctx: &mut Context, spawn: Point2<f32>, map_size: (f32, f32)) -> GameResult<Fabien> {
+        // The map always keeps some room around the spawn
+        let hitbox = Rect::new(spawn.x - 4.0, spawn.y - 8.0, 8.0, 16.0);
         let mut sprites = HashMap::new();
 
         for facing in ["front", "back", "right", "left"].iter() {
@@ -68,13 +70,6 @@ impl Fabien {
             }
         }
 
-        let camera_size = (screen_size.0 / 4.5, screen_size.1 / 4.5);
-        let camera = Rect::new(
-            hitbox.x - camera_size.0 / 2.0,
-            hitbox.y - camera_size.1 / 2.0,
-            camera_size.0, camera_size.1
-        );
-
         let bullet_sprite = graphics::Image::new(ctx, "/bullet.png")?;
         let sandwich_sprite = graphics::Image::new(ctx, "/sandwich.png")?;
         let moldy_sandwich_sprite = graphics::Image::new(ctx, "/moldy_sandwich.png")?;
@@ -88,23 +83,23 @@ impl Fabien {
             piercing_bullet_sprite: piercing_bullet_sprite,
             facing: "front".to_string(),
             hitbox: hitbox,
-            camera: camera,
             shooting: (false, 0.0),
             ammos: 44,
             starting_ammos: 44,
             score: 0,
+            coins: 0,
             health: 10,
             max_health: 10,
             animation_cycle: 0,
             animation_time: 0.0,
             speed: 50.0,
             starting_speed: 50.0,
-            active_powerup: None,
-            powerup_sprite: None,
+            active_powerups: vec![],
             movement_queue: VecDeque::new(),
             map_size: map_size,
             shots: VecDeque::<Bullet>::new(),
             particles: vec![],
+            noise: None,
             invicibility_frames: 0
         };
 
@@ -135,37 +130,25 @@ impl Fabien {
             .dest(Point2::new(self.hitbox.x - 3.0, self.hitbox.y));
         graphics::draw(ctx, sprite, param)?;
 
+        // Drawing particles
+        for p in self.particles.iter() { p.draw(ctx)?; }
+
         Ok(())
     }
 
-    pub fn draw_infos(&mut self, ctx: &mut Context) -> GameResult {
-        // Update the camera view
-        self.camera.x = self.hitbox.x - self.camera.w / 2.0;
-        self.camera.y = self.hitbox.y - self.camera.h / 2.0;
-
-        if self.camera.x <= 0.0 { self.camera.x = 0.0; }
-        else if self.camera.x >= self.map_size.0 - self.camera.w {
-            self.camera.x = self.map_size.0 - self.camera.w;
-        }
-
-        if self.camera.y <= 0.0 { self.camera.y = 0.0 }
-        else if self.camera.y >= self.map_size.1 - self.camera.h {
-            self.camera.y = self.map_size.1 - self.camera.h;
-        }
-
-        graphics::set_screen_coordinates(ctx, self.camera)?;
-
+    // The ammos, the health and the powerups, around the edges of the screen
+    pub fn draw_infos(&mut self, ctx: &mut Context, ui: &Ui) -> GameResult {
         // Drawing remaining ammos
         {
-            const BULLET_SCALE: f32 = 0.7;
+            const BULLET_SCALE: f32 = 2.1;
             const BULLET_SPACING: f32 = BULLET_SCALE / 1.8;
             let bullet_width = self.bullet_sprite.width() as f32;
             let bullet_height = self.bullet_sprite.height() as f32;
-            let bullet_origin = (self.camera.x + 1.0, self.camera.y + 1.0);
+            let bullet_origin = ui.place(Anchor::TopLeft, (0.0, 0.0), (3.0, 3.0));
             let mut param = graphics::DrawParam::default()
                     .scale(Vector2::new(BULLET_SCALE, BULLET_SCALE));
 
-            let bullet_sprite = if let Some(Powerups::PiercingBullet(_)) = self.active_powerup {
+            let bullet_sprite = if self.has_powerup(|p| matches!(p, Powerups::PiercingBullet(_))) {
                 &self.piercing_bullet_sprite
             } else {
                 &self.bullet_sprite
@@ -175,8 +158,8 @@ impl Fabien {
             let mut j = 0;
             for _ in 0..self.ammos {
                 param = param.dest(Point2::new(
-                        bullet_origin.0 + (bullet_width * BULLET_SPACING) * i as f32,
-                        bullet_origin.1 + (bullet_height * BULLET_SPACING) * j as f32
+                        bullet_origin.x + (bullet_width * BULLET_SPACING) * i as f32,
+                        bullet_origin.y + (bullet_height * BULLET_SPACING) * j as f32
                     ));
                 graphics::draw(ctx, bullet_sprite, param)?;
                 if i % 10 == 0 && i != 0 {
@@ -188,18 +171,18 @@ impl Fabien {
 
         // Drawing health
         {
-            const SANDWICH_SCALE: f32 = 0.065;
+            const SANDWICH_SCALE: f32 = 0.2;
             const SANDWICH_SPACING: f32 = SANDWICH_SCALE / 1.5;
             let sandwich_width = self.sandwich_sprite.width() as f32;
-            let sandwich_origin = (self.camera.x + self.camera.w - 1.0, self.camera.y - 1.0);
+            let sandwich_origin = ui.place(Anchor::TopRight, (0.0, 0.0), (3.0, 0.0));
             let mut param = graphics::DrawParam::default()
                 .scale(Vector2::new(SANDWICH_SCALE, SANDWICH_SCALE));
 
             for i in (0..self.max_health).rev() {
                 param = param.dest(Point2::new(
-                    sandwich_origin.0 - sandwich_width * SANDWICH_SCALE 
+                    sandwich_origin.x - sandwich_width * SANDWICH_SCALE 
                         - (sandwich_width * SANDWICH_SPACING) * i as f32,
-                    sandwich_origin.1
+                    sandwich_origin.y
                 ));
                 let to_draw = if i + 1 > self.health {
                     &self.moldy_sandwich_sprite
@@ -209,47 +192,45 @@ impl Fabien {
             }
         }
 
-        // If Fabien has an active powerup, display the icon with the remaining time
-        if let Some(powerup) = &self.active_powerup {
+        // Display the icon of each active powerup with the remaining time (or hits
+        // for the shield), stacked from the bottom right corner
+        let corner = ui.place(Anchor::BottomRight, (0.0, 0.0), (3.0, 3.0));
+        let mut icon_y = corner.y;
+        for (powerup, sprite) in self.active_powerups.iter() {
             let timer = match powerup {
-                Powerups::SpeedBoost((time, _)) => time.ceil(),
-                Powerups::PiercingBullet((time, _)) => time.ceil(),
-                _ => 0.0
+                Powerups::Shield(hits) => format!("x{}", hits),
+                Powerups::PiercingBullet((time, _)) | Powerups::SpeedBoost((time, _, _))
+                | Powerups::TimeFreeze(time) | Powerups::Multishot((time, _, _))
+                | Powerups::Magnet((time, _, _)) | Powerups::ExplosiveRounds((time, _)) => time.ceil().to_string(),
+                _ => String::new()
             };
             
-            const SCALE: f32 = 0.1;
-            let sprite = self.powerup_sprite.as_ref().unwrap();
-            let sprite_pos = Point2::new(self.camera.x + self.camera.w - sprite.width() as f32 - 1.0,
-                    self.camera.y + self.camera.h - sprite.height() as f32 - 1.0);
-
-            let mut timer_text = Text::new(ctx, timer.to_string(), "/Fonts/arial_narrow_7.ttf".to_string(),
+            const SCALE: f32 = 0.3;
+            const ICON_SCALE: f32 = 3.0;
+            let (icon_width, icon_height) = (sprite.width() as f32 * ICON_SCALE, sprite.height() as f32 * ICON_SCALE);
+            icon_y -= icon_height;
+            let sprite_pos = Point2::new(corner.x - icon_width, icon_y);
+            icon_y -= 6.0;
+
+            let mut timer_text = Text::new(ctx, timer, "/Fonts/arial_narrow_7.ttf".to_string(),
                                         100.0, graphics::Color::from_rgb(255, 255, 255))?;
             timer_text.set_pos(Point2::new(sprite_pos.x - timer_text.width(ctx) * SCALE - SCALE * 20.0,
-                    sprite_pos.y + sprite.height() as f32 / 2.0 - (timer_text.height(ctx) * SCALE / 2.0)));
+                    sprite_pos.y + icon_height / 2.0 - (timer_text.height(ctx) * SCALE / 2.0)));
 
             graphics::queue_text(ctx, timer_text.get_ggez_text(), Point2::new(0.0, 0.0), None);
             graphics::draw_queued_text(ctx, graphics::DrawParam::new()
                 .scale(ggez::nalgebra::Vector2::new(SCALE, SCALE))
                 .dest(timer_text.get_pos()), None, graphics::FilterMode::Nearest)?;
 
-            graphics::draw(ctx, sprite, graphics::DrawParam::default().dest(sprite_pos))?;
+            graphics::draw(ctx, sprite, graphics::DrawParam::default()
+                .dest(sprite_pos)
+                .scale(Vector2::new(ICON_SCALE, ICON_SCALE)))?;
         }
 
-        // Drawing particles
-        for p in self.particles.iter() { p.draw(ctx)?; }
-
-        // let hitbox = graphics::Mesh::new_rectangle(
-        //     ctx,
-        //     graphics::DrawMode::stroke(0.5),
-        //     self.hitbox,
-        //     graphics::Color::from_rgb(0, 0, 0)
-        // )?;
-        // graphics::draw(ctx, &hitbox, graphics::DrawParam::new().dest(Point2::new(0.0, 0.0)))?;
-
         Ok(())
     }
 
-    pub fn update(&mut self, ctx: &mut Context, trees: &mut Vec<Tree>) -> GameResult {
+    pub fn update(&mut self, ctx: &mut Context, map: &Map) -> GameResult {
         let dt = ggez::timer::delta(ctx).as_secs_f32();
 
         // Update the invicibility_frames if Fabien is currently invicible
@@ -266,8 +247,10 @@ impl Fabien {
             }
         }
 
-        let vel_x = dir.x * self.speed * dt;
-        let vel_y = dir.y * self.speed * dt;
+        // Bushes and mud slow him down
+        let speed = self.speed * map.speed_factor(self.hitbox);
+        let vel_x = dir.x * speed * dt;
+        let vel_y = dir.y * speed * dt;
         self.hitbox.x += vel_x;
         self.hitbox.y += vel_y;
 
@@ -277,51 +260,23 @@ impl Fabien {
         if self.hitbox.y < 0.0 { self.hitbox.y = 0.0; }
         else if self.hitbox.y + self.hitbox.h > self.map_size.1 { self.hitbox.y = self.map_size.1 - self.hitbox.h; }
 
-        // Check if Fabien is now in a tree, if so move him back
-        for tree in trees.iter_mut() {
-            let tree_hitbox = tree.get_hitbox();
-            // If the tree is more than 50 units away from the player,
-            // we don't consider it.
-            if (tree_hitbox.x < self.hitbox.x - 50.0 ||
-                tree_hitbox.x > self.hitbox.x + 50.0) &&
-               (tree_hitbox.y < self.hitbox.y - 50.0 ||
-                tree_hitbox.y > self.hitbox.y + 50.0)
-                { continue; }
-
-            if self.hitbox.y > tree_hitbox.y {
-                tree.draw_before_fabien(true);
-            } else if !self.hitbox.overlaps(&tree_hitbox) {
-                tree.draw_before_fabien(false);
-            } else {
-                self.hitbox.x -= vel_x;
-                self.hitbox.y -= vel_y;
-            }
+        // Check if Fabien is now in something he can't walk through, if so move him back
+        if map.blocks_walking(self.hitbox) {
+            self.hitbox.x -= vel_x;
+            self.hitbox.y -= vel_y;
         }
 
-        // Update the time left of the speed boost and piercing bullets powerups
-        match self.active_powerup {
-            Some(Powerups::SpeedBoost((ref mut time_left, _))) => {
-                if time_left <= &mut 0.0 { self.active_powerup = None; }
-                else { *time_left -= dt; }
-            },
-            Some(Powerups::PiercingBullet((ref mut time_left, _))) => {
-                if time_left <= &mut 0.0 { self.active_powerup = None; }
-                else { *time_left -= dt; }
-            },
-            _ => self.speed = self.starting_speed
+        // Update the time left of the powerups, and forget about the ones that are over
+        for (powerup, _) in self.active_powerups.iter_mut() {
+            if let Some(time_left) = powerup.time_left() { *time_left -= dt; }
+        }
+        self.active_powerups.retain(|(powerup, _)| !powerup.is_over());
+        if !self.has_powerup(|p| matches!(p, Powerups::SpeedBoost(_))) {
+            self.speed = self.starting_speed;
         }
 
         if self.shooting.0 { self.shooting.1 += dt; }
-        if self.shots.len() > 0 {
-            let mut to_remove: Option<usize> = None;
-            for (i, b) in self.shots.iter_mut().enumerate() {
-                if !b.update(ctx) { 
-                    to_remove = Some(i);
-                    break;
-                }
-            }
-            if let Some(x) = to_remove { self.shots.remove(x); }
-        }
+        self.shots.retain_mut(|b| b.update(ctx));
         if self.shooting.1 > 0.4 { self.shooting.0 = false; self.shooting.1 = 0.0; }
 
         // Update the particles
@@ -331,19 +286,29 @@ impl Fabien {
         Ok(())
     }
 
-    pub fn take_hit(&mut self) -> bool {
-        if self.invicibility_frames <= 0 {
-            self.health -= 1;
-            self.invicibility_frames = 30;
-            true
-        } else { false }
+    pub fn take_hit(&mut self) -> Hit {
+        if self.invicibility_frames > 0 { return Hit::Ignored; }
+        self.invicibility_frames = 30;
+
+        for (powerup, _) in self.active_powerups.iter_mut() {
+            if let Powerups::Shield(hits) = powerup {
+                if *hits > 0 {
+                    *hits -= 1;
+                    return Hit::Absorbed;
+                }
+            }
+        }
+
+        self.health -= 1;
+        Hit::Taken
     }
 
     pub fn add_to_score(&mut self, to_add: u32) {
         self.score += to_add;
     }
 
-    pub fn activate_powerup(&mut self, ctx: &mut Context, powerup: Powerups) -> GameResult {
+    // The sprite is the one of the picked up powerup, shown with the time left
+    pub fn activate_powerup(&mut self, powerup: Powerups, sprite: &graphics::Image) {
         match powerup {
             Powerups::Heal(health) => {
                 if self.health + health > self.max_health {
@@ -353,32 +318,46 @@ impl Fabien {
                 }
             },
             Powerups::AmmoRestock(nb_ammos) => self.ammos += nb_ammos,
-            Powerups::SpeedBoost((new_time, speed_mult)) => {
-                self.active_powerup = Some(powerup);
-                self.speed *= speed_mult;
-                if self.speed > 170.0 {
-                    self.speed = 170.0;
-                    if let Some(Powerups::SpeedBoost((ref mut timer, _))) = self.active_powerup {
-                        *timer += new_time;
-                    }
-                }
-                self.powerup_sprite = Some(graphics::Image::new(ctx, "/speed_powerup.png")?);
+            Powerups::Coin(value) => self.coins += value,
+            Powerups::SpeedBoost((_, speed_mult, max_speed)) => {
+                self.speed = (self.speed * speed_mult).min(max_speed);
+                self.add_active_powerup(powerup, sprite);
             },
-            Powerups::PiercingBullet((new_time, _)) => {
-                if let Some(Powerups::SpeedBoost(_)) = self.active_powerup {
-                    self.speed = self.starting_speed;
+            Powerups::Shield(new_hits) => {
+                match self.active_powerups.iter_mut().find(|(p, _)| matches!(p, Powerups::Shield(_))) {
+                    Some((Powerups::Shield(hits), _)) => *hits = hits.saturating_add(new_hits),
+                    _ => self.active_powerups.push((powerup, sprite.clone()))
                 }
+            },
+            _ => self.add_active_powerup(powerup, sprite)
+        }
+    }
 
-                if let Some(Powerups::PiercingBullet((ref mut time, _))) = self.active_powerup {
-                    *time += new_time;
-                } else {
-                    self.active_powerup = Some(powerup);
-                    self.powerup_sprite = Some(graphics::Image::new(ctx, "/piercing_bullet.png")?);
-                }
-            }
+    // Picking up one that's already active only adds to its time
+    fn add_active_powerup(&mut self, mut powerup: Powerups, sprite: &graphics::Image) {
+        let new_time = powerup.time_left().map_or(0.0, |time| *time);
+        match self.active_powerups.iter_mut().find(|(p, _)| p.same_kind(&powerup)) {
+            Some((active, _)) => {
+                if let Some(time) = active.time_left() { *time += new_time; }
+            },
+            None => self.active_powerups.push((powerup, sprite.clone()))
         }
+    }
 
-        Ok(())
+    fn has_powerup(&self, which: impl Fn(&Powerups) -> bool) -> bool {
+        self.active_powerups.iter().any(|(p, _)| which(p))
+    }
+
+    pub fn is_time_frozen(&self) -> bool {
+        self.has_powerup(|p| matches!(p, Powerups::TimeFreeze(_)))
+    }
+
+    // Radius and strength of the magnet, if Fabien has one
+    pub fn get_magnet(&self) -> Option<(f32, f32)> {
+        self.active_powerups.iter().find_map(|(p, _)| match p {
+            Powerups::Magnet((_, radius, strength)) => Some((*radius, *strength)),
+            _ => None
+        })
     }
 
     pub fn key_down_event(&mut self, keycode: KeyCode) -> GameResult {
@@ -403,21 +382,13 @@ impl Fabien {
         }
     }
 
-    pub fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton,
-        x: f32, y: f32, screen_size: (f32, f32))
-    {
+    // Where the click is on the map, see Camera::screen_to_world
+    pub fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, pos: Point2<f32>) {
         if let MouseButton::Left = button {
-            let ratio_x = screen_size.0 / self.camera.w;
-            let ratio_y = screen_size.1 / self.camera.h;
-            self.shoot(ctx, self.camera.x + x / ratio_x, self.camera.y + y / ratio_y).unwrap();
+            self.shoot(ctx, pos.x, pos.y).unwrap();
         }
     }
 
-    pub fn resize_event(&mut self, width: f32, height: f32) {
-        self.camera.w = width / 4.5;
-        self.camera.h = height / 4.5;
-    }
-
     fn shoot(&mut self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
         if !self.shooting.0 && self.ammos > 0 { 
             self.shooting.0 = true;
@@ -434,20 +405,38 @@ impl Fabien {
 
             let angle = (y - pos.1).atan2(x - pos.0);
 
-            let nb_pierce = if let Some(Powerups::PiercingBullet((_, nb))) = self.active_powerup {
-                nb
-            } else { 0 };
-
-            let bullet = Bullet::new(
-                ctx,
-                BULLET_SPEED,
-                angle,
-                Rect::new(pos.0, pos.1, 1.0, 1.0),
-                nb_pierce as i8,
-                5.0
-            ).unwrap();
+            let mut nb_pierce = 0;
+            let mut multishot = (1, 0.0);
+            let mut explosion_radius = None;
+            for (powerup, _) in self.active_powerups.iter() {
+                match powerup {
+                    Powerups::PiercingBullet((_, nb)) => nb_pierce = *nb,
+                    Powerups::Multishot((_, nb_bullets, spread)) => multishot = (*nb_bullets.max(&1), *spread),
+                    Powerups::ExplosiveRounds((_, radius)) => explosion_radius = Some(*radius),
+                    _ => {}
+                }
+            }
 
-            self.shots.push_back(bullet);
+            // With multishot, the bullets are spread evenly around where Fabien aims
+            for i in 0..multishot.0 {
+                let offset = if multishot.0 > 1 {
+                    -multishot.1 / 2.0 + multishot.1 * i as f32 / (multishot.0 - 1) as f32
+                } else { 0.0 };
+
+                let mut bullet = Bullet::new(
+                    ctx,
+                    BULLET_SPEED,
+                    angle + offset,
+                    Rect::new(pos.0, pos.1, 1.0, 1.0),
+                    nb_pierce as i8,
+                    5.0
+                ).unwrap();
+                if let Some(radius) = explosion_radius { bullet.set_explosive(radius); }
+
+                self.shots.push_back(bullet);
+            }
+            // Gunshots are loud, Bertrands around will come and check
+            self.noise = Some(Point2::new(pos.0, pos.1));
 
             const NB_PARTICLES: usize = 10;
 
@@ -475,13 +464,31 @@ impl Fabien {
         Ok(())
     }
 
+    pub fn take_noise(&mut self) -> Option<Point2<f32>> {
+        self.noise.take()
+    }
+
     pub fn is_shooting(&self) -> bool {
         self.shooting.0
     }
 
-    pub fn reset(&mut self, screen_size: (f32, f32)) {
-        self.hitbox.x = screen_size.0 / 2.0;
-        self.hitbox.y = screen_size.1 / 2.0;
+    // A new map can have another size, and Fabien starts at its spawn point
+    pub fn set_map(&mut self, map_size: (f32, f32), spawn: Point2<f32>) {
+        self.map_size = map_size;
+        self.hitbox.x = spawn.x - self.hitbox.w / 2.0;
+        self.hitbox.y = spawn.y - self.hitbox.h / 2.0;
+    }
+
+    pub fn apply_difficulty(&mut self, difficulty: Difficulty, mode: GameMode) {
+        self.max_health = mode.max_health().unwrap_or_else(|| difficulty.max_health());
+        self.starting_ammos = difficulty.starting_ammos();
+        self.health = self.max_health;
+        self.ammos = self.starting_ammos;
+    }
+
+    pub fn reset(&mut self, spawn: Point2<f32>) {
+        self.hitbox.x = spawn.x - self.hitbox.w / 2.0;
+        self.hitbox.y = spawn.y - self.hitbox.h / 2.0;
         self.ammos = self.starting_ammos;
         self.speed = self.starting_speed;
         self.shooting = (false, 0.0);
@@ -489,12 +496,18 @@ impl Fabien {
         self.movement_queue.clear();
         self.facing = "front".to_string();
         self.score = 0;
+        self.coins = 0;
+        self.active_powerups.clear();
     }
 
     pub fn get_hitbox(&self) -> Rect {
         self.hitbox
     }
 
+    pub fn get_shot_hitboxes(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.shots.iter().map(|b| b.get_hitbox())
+    }
+
     pub fn get_shots(&mut self) -> &mut VecDeque<Bullet> {
         &mut self.shots
     }
@@ -503,6 +516,10 @@ impl Fabien {
         self.health
     }
 
+    pub fn get_max_health(&self) -> u8 {
+        self.max_health
+    }
+
     pub fn get_score(&self) -> u32 {
         self.score
     }
@@ -511,13 +528,14 @@ impl Fabien {
         self.ammos
     }
 
+    pub fn get_coins(&self) -> u32 {
+        self.coins
+    }
+
     pub fn clear_movement(&mut self) {
         self.movement_queue.clear();
     }
 
-    pub fn get_camera(&self) -> Rect {
-        self.camera
-    }
 
     pub fn set_health(&mut self, health: u8) {
         self.health = health;
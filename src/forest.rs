@@ -0,0 +1,279 @@
+use ggez::nalgebra::Point2;
+use rand::Rng;
+use crate::utils::game_rng;
+use crate::terrain::ObstacleKind;
+
+// Trees are tried on a grid of that size, moved around a bit so it doesn't look like a grid
+const TREE_STEP: f32 = 22.0;
+// No two trees closer than this (from trunk to trunk)
+const MIN_SPACING: f32 = 19.0;
+// Size of the noise lattice, the bigger the larger the groves
+const NOISE_SCALE: f32 = 260.0;
+// Where the noise turns from open woods into a dense grove
+const GROVE_START: f32 = 0.55;
+const GROVE_FULL: f32 = 0.75;
+// Even outside of the groves there's a tree here and there
+const SCATTER_DENSITY: f32 = 0.02;
+// Nothing grows that close to where Fabien starts
+const SPAWN_RADIUS: f32 = 110.0;
+// One clearing for that many square units of map
+const CLEARING_AREA: f32 = 650_000.0;
+const CLEARING_RADIUS: (f32, f32) = (70.0, 150.0);
+// Paths are this wide, trees included
+pub const PATH_WIDTH: f32 = 36.0;
+// Length of each bit of path, and how much it can turn between two of them
+const PATH_STEP: f32 = 40.0;
+const PATH_WIGGLE: f32 = 0.5;
+// Rocks are anywhere, but there aren't many of them
+const ROCK_DENSITY: f32 = 0.015;
+// Bushes grow around the groves, where it's not quite dense enough for trees
+const BUSH_BAND: f32 = 0.4;
+const BUSH_DENSITY: f32 = 0.12;
+// One pond for that many square units of map
+const POND_AREA: f32 = 1_300_000.0;
+const POND_RADIUS: (f32, f32) = (40.0, 85.0);
+// Chance for each point of a path to have a puddle of mud
+const MUD_CHANCE: f32 = 0.12;
+const MUD_RADIUS: (f32, f32) = (14.0, 30.0);
+
+// Smooth random values on a lattice, blended between the points
+struct ValueNoise {
+    cols: usize,
+    values: Vec<f32>
+}
+
+impl ValueNoise {
+    fn new(width: f32, height: f32, scale: f32) -> ValueNoise {
+        let cols = (width / scale).ceil() as usize + 2;
+        let rows = (height / scale).ceil() as usize + 2;
+        let values = game_rng(|rng| (0..cols * rows).map(|_| rng.gen::<f32>()).collect());
+
+        ValueNoise { cols: cols, values: values }
+    }
+
+    // Between 0 and 1, x and y are in lattice units
+    fn get(&self, x: f32, y: f32) -> f32 {
+        let (col, row) = (x.floor() as usize, y.floor() as usize);
+        let (tx, ty) = (smoothstep(x.fract()), smoothstep(y.fract()));
+        let value = |c: usize, r: usize| self.values[r * self.cols + c];
+
+        let top = value(col, row) + (value(col + 1, row) - value(col, row)) * tx;
+        let bottom = value(col, row + 1) + (value(col + 1, row + 1) - value(col, row + 1)) * tx;
+        top + (bottom - top) * ty
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Where the obstacles go and what's kept open, before anything gets loaded
+pub struct Forest {
+    // Which sprite, and where its top left corner is
+    pub obstacles: Vec<(usize, Point2<f32>)>,
+    pub paths: Vec<Vec<Point2<f32>>>,
+    pub spawn: Point2<f32>,
+    width: f32,
+    height: f32
+}
+
+impl Forest {
+    // sprites are the kind and size of each sprite that can be used, the trunk
+    // being the bottom middle of the sprite (the flat ones are tiles), and
+    // tree_density multiplies the chance of a tree growing. Everything is rolled
+    // from the game's RNG, so the same seed always grows the same forest.
+    pub fn generate(width: f32, height: f32, tree_density: f32, sprites: &[(ObstacleKind, (f32, f32))]) -> Forest {
+        let of_kind = |kind: ObstacleKind| -> Vec<usize> {
+            sprites.iter().enumerate().filter(|(_, (k, _))| *k == kind).map(|(i, _)| i).collect()
+        };
+        let (trees, rocks, bushes) = (of_kind(ObstacleKind::Tree), of_kind(ObstacleKind::Rock), of_kind(ObstacleKind::Bush));
+        let (water, mud) = (of_kind(ObstacleKind::Water), of_kind(ObstacleKind::Mud));
+
+        let spawn = Point2::new(width / 2.0, height / 2.0);
+        let noise = ValueNoise::new(width, height, NOISE_SCALE);
+        let detail = ValueNoise::new(width, height, NOISE_SCALE / 3.0);
+
+        // A few clearings, each one linked to the spawn by a path, and a
+        // few more paths going off to the edges of the map
+        let nb_clearings = ((width * height / CLEARING_AREA).round() as usize).max(1);
+        let clearings: Vec<(Point2<f32>, f32)> = game_rng(|rng| (0..nb_clearings).map(|_| (
+            Point2::new(rng.gen_range(0.1..0.9) * width, rng.gen_range(0.1..0.9) * height),
+            rng.gen_range(CLEARING_RADIUS.0..CLEARING_RADIUS.1)
+        )).collect());
+
+        let mut paths: Vec<Vec<Point2<f32>>> = clearings.iter()
+            .map(|(center, _)| winding_path(spawn, *center))
+            .collect();
+        for side in 0..4 {
+            let end = game_rng(|rng| match side {
+                0 => Point2::new(rng.gen_range(0.2..0.8) * width, 0.0),
+                1 => Point2::new(width, rng.gen_range(0.2..0.8) * height),
+                2 => Point2::new(rng.gen_range(0.2..0.8) * width, height),
+                _ => Point2::new(0.0, rng.gen_range(0.2..0.8) * height)
+            });
+            paths.push(winding_path(spawn, end));
+        }
+
+        let mut forest = Forest {
+            obstacles: vec![],
+            paths: paths,
+            spawn: spawn,
+            width: width,
+            height: height
+        };
+
+        // Ponds, away from the spawn. The paths go through them, like a ford
+        let nb_ponds = (width * height / POND_AREA).round() as usize;
+        let ponds: Vec<(Point2<f32>, f32)> = game_rng(|rng| (0..nb_ponds).map(|_| (
+            Point2::new(rng.gen_range(0.1..0.9) * width, rng.gen_range(0.1..0.9) * height),
+            rng.gen_range(POND_RADIUS.0..POND_RADIUS.1)
+        )).collect::<Vec<_>>()).into_iter()
+            .filter(|(center, radius)| distance(*center, spawn) > SPAWN_RADIUS * 2.0 + radius)
+            .collect();
+        if let Some(&tile) = water.first() {
+            let size = sprites[tile].1;
+            for (center, radius) in ponds.iter() {
+                for position in round_patch(size, *center, *radius, &detail) {
+                    let middle = Point2::new(position.x + size.0 / 2.0, position.y + size.1 / 2.0);
+                    if !forest.is_on_path(middle, size.0 / 2.0) { forest.add_tile(tile, size, position); }
+                }
+            }
+        }
+
+        // And a bit of mud here and there on the paths
+        if let Some(&tile) = mud.first() {
+            let puddles: Vec<(Point2<f32>, f32)> = forest.paths.iter()
+                .flat_map(|path| path.iter().skip(2))
+                .filter_map(|point| game_rng(|rng| if rng.gen::<f32>() < MUD_CHANCE {
+                    Some((*point, rng.gen_range(MUD_RADIUS.0..MUD_RADIUS.1)))
+                } else { None }))
+                .collect();
+            for (center, radius) in puddles {
+                for position in round_patch(sprites[tile].1, center, radius, &detail) {
+                    forest.add_tile(tile, sprites[tile].1, position);
+                }
+            }
+        }
+
+        // Trees (and rocks and bushes) already placed, by cell of MIN_SPACING, to check the spacing quickly
+        let spacing_cols = (width / MIN_SPACING).ceil() as usize + 1;
+        let spacing_rows = (height / MIN_SPACING).ceil() as usize + 1;
+        let mut placed: Vec<Vec<Point2<f32>>> = vec![vec![]; spacing_cols * spacing_rows];
+
+        let mut y = 0.0;
+        while y < height {
+            let mut x = 0.0;
+            while x < width {
+                let (pick, jitter, roll) = game_rng(|rng| (
+                    rng.gen::<usize>(),
+                    (rng.gen_range(-0.4..0.4) * TREE_STEP, rng.gen_range(-0.4..0.4) * TREE_STEP),
+                    rng.gen::<f32>()
+                ));
+                let trunk = Point2::new(x + jitter.0, y + jitter.1);
+                x += TREE_STEP;
+
+                let density = (noise.get(trunk.x / NOISE_SCALE, trunk.y / NOISE_SCALE) * 0.75
+                    + detail.get(trunk.x * 3.0 / NOISE_SCALE, trunk.y * 3.0 / NOISE_SCALE) * 0.25
+                    - GROVE_START) / (GROVE_FULL - GROVE_START);
+
+                // A tree in the groves, a bush on their edges and a rock once in a while
+                let choices = if roll < density.clamp(SCATTER_DENSITY, 1.0) * tree_density {
+                    &trees
+                } else if density > -BUSH_BAND && roll > 1.0 - BUSH_DENSITY {
+                    &bushes
+                } else if roll > 1.0 - ROCK_DENSITY {
+                    &rocks
+                } else {
+                    continue;
+                };
+                if choices.is_empty() { continue; }
+                let sprite = choices[pick % choices.len()];
+
+                let (sprite_width, sprite_height) = sprites[sprite].1;
+                let position = Point2::new(trunk.x - sprite_width / 2.0, trunk.y - sprite_height);
+                if position.x < 0.0 || position.y < 0.0
+                    || position.x + sprite_width > width || trunk.y > height { continue; }
+
+                // Keep the spawn, the clearings, the ponds and the paths open
+                if distance(trunk, spawn) < SPAWN_RADIUS + sprite_height
+                    || clearings.iter().any(|(center, radius)| distance(trunk, *center) < *radius)
+                    || ponds.iter().any(|(center, radius)| distance(trunk, *center) < radius + MIN_SPACING)
+                    || forest.is_on_path(trunk, sprite_width / 2.0) { continue; }
+
+                let (col, row) = ((trunk.x / MIN_SPACING) as usize, (trunk.y / MIN_SPACING) as usize);
+                let too_close = (row.saturating_sub(1)..=row + 1).any(|r| (col.saturating_sub(1)..=col + 1)
+                    .any(|c| r < spacing_rows && c < spacing_cols
+                        && placed[r * spacing_cols + c].iter().any(|other| distance(trunk, *other) < MIN_SPACING)));
+                if too_close { continue; }
+
+                placed[row * spacing_cols + col].push(trunk);
+                forest.obstacles.push((sprite, position));
+            }
+            y += TREE_STEP;
+        }
+
+        forest
+    }
+
+    // Two patches can overlap, and nothing goes past the edges
+    fn add_tile(&mut self, tile: usize, size: (f32, f32), position: Point2<f32>) {
+        if position.x < 0.0 || position.y < 0.0
+            || position.x + size.0 > self.width || position.y + size.1 > self.height { return; }
+        if self.obstacles.iter().any(|(_, other)| *other == position) { return; }
+
+        self.obstacles.push((tile, position));
+    }
+
+    // Is this point on a path, give or take the margin
+    pub fn is_on_path(&self, point: Point2<f32>, margin: f32) -> bool {
+        self.paths.iter().any(|path| path.windows(2)
+            .any(|segment| distance_to_segment(point, segment[0], segment[1]) < PATH_WIDTH / 2.0 + margin))
+    }
+}
+
+// The tiles (top left corners, on a grid of their size) covering a roughly
+// round patch, the edge made a bit irregular by the noise
+fn round_patch(size: (f32, f32), center: Point2<f32>, radius: f32, noise: &ValueNoise) -> Vec<Point2<f32>> {
+    let (cols, rows) = ((radius / size.0).ceil() as i32, (radius / size.1).ceil() as i32);
+    let origin = ((center.x / size.0).round() * size.0, (center.y / size.1).round() * size.1);
+
+    let mut tiles = vec![];
+    for row in -rows..=rows {
+        for col in -cols..=cols {
+            let position = Point2::new(origin.0 + col as f32 * size.0, origin.1 + row as f32 * size.1);
+            let middle = Point2::new(position.x + size.0 / 2.0, position.y + size.1 / 2.0);
+            let edge = radius * (0.75 + noise.get(middle.x * 3.0 / NOISE_SCALE, middle.y * 3.0 / NOISE_SCALE) * 0.25);
+            if distance(middle, center) <= edge { tiles.push(position); }
+        }
+    }
+
+    tiles
+}
+
+// From start to end, turning a bit left and right on the way
+fn winding_path(start: Point2<f32>, end: Point2<f32>) -> Vec<Point2<f32>> {
+    let mut path = vec![start];
+    let mut current = start;
+
+    while distance(current, end) > PATH_STEP {
+        let heading = (end.y - current.y).atan2(end.x - current.x)
+            + game_rng(|rng| rng.gen_range(-PATH_WIGGLE..PATH_WIGGLE));
+        current = Point2::new(current.x + heading.cos() * PATH_STEP, current.y + heading.sin() * PATH_STEP);
+        path.push(current);
+    }
+    path.push(end);
+
+    path
+}
+
+fn distance(a: Point2<f32>, b: Point2<f32>) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+fn distance_to_segment(point: Point2<f32>, a: Point2<f32>, b: Point2<f32>) -> f32 {
+    let length = (b.x - a.x).powi(2) + (b.y - a.y).powi(2);
+    if length == 0.0 { return distance(point, a); }
+
+    let t = (((point.x - a.x) * (b.x - a.x) + (point.y - a.y) * (b.y - a.y)) / length).clamp(0.0, 1.0);
+    distance(point, Point2::new(a.x + t * (b.x - a.x), a.y + t * (b.y - a.y)))
+}
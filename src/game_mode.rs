@@ -0,0 +1,92 @@
+use std::env;
+use crate::difficulty::Difficulty;
+use crate::powerup::EffectDef;
+
+// How long a Time Attack game lasts, in seconds
+const TIME_ATTACK_DURATION: f64 = 300.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameMode {
+    // The original endless mode, survive as long as possible
+    Survival,
+    // Highest score in 5 minutes
+    TimeAttack,
+    // No ammo restock lying around, ammos only come from dead Bertrands
+    Scavenger,
+    // A single health point and no heals, one hit and it's over
+    Hardcore
+}
+
+impl GameMode {
+    // The one after this one, to cycle through them in the settings
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Survival => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Scavenger,
+            GameMode::Scavenger => GameMode::Hardcore,
+            GameMode::Hardcore => GameMode::Survival
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "Survie",
+            GameMode::TimeAttack => "Contre-la-montre",
+            GameMode::Scavenger => "Pillard",
+            GameMode::Hardcore => "Hardcore"
+        }
+    }
+
+    pub fn time_limit(&self) -> Option<f64> {
+        match self {
+            GameMode::TimeAttack => Some(TIME_ATTACK_DURATION),
+            _ => None
+        }
+    }
+
+    // Overrides the max health given by the difficulty
+    pub fn max_health(&self) -> Option<u8> {
+        match self {
+            GameMode::Hardcore => Some(1),
+            _ => None
+        }
+    }
+
+    // Can this powerup show up on its own somewhere on the map
+    pub fn allows_spawn(&self, effect: &EffectDef) -> bool {
+        !matches!((self, effect), (GameMode::Scavenger, EffectDef::AmmoRestock { .. })
+            | (GameMode::Hardcore, EffectDef::Heal { .. }))
+    }
+
+    // Can a Bertrand leave this powerup behind him when he dies
+    pub fn allows_drop(&self, effect: &EffectDef) -> bool {
+        !matches!((self, effect), (GameMode::Hardcore, EffectDef::Heal { .. }))
+    }
+
+    pub fn is_over(&self, time_passed: f64, health: u8) -> bool {
+        health == 0 || self.time_limit().is_some_and(|limit| time_passed >= limit)
+    }
+
+    // The survival stats keep their original keys, the other modes get their own
+    pub fn stats_prefix(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "",
+            GameMode::TimeAttack => "time_attack_",
+            GameMode::Scavenger => "scavenger_",
+            GameMode::Hardcore => "hardcore_"
+        }
+    }
+
+    // Survival uses the leaderboards of the difficulties, the other modes have
+    // one per difficulty too, named like TABLE_ID_HARDCORE_NORMAL in the .env
+    pub fn table_id(&self, difficulty: Difficulty) -> Option<String> {
+        let mode = match self {
+            GameMode::Survival => return difficulty.table_id(),
+            GameMode::TimeAttack => "TIME_ATTACK",
+            GameMode::Scavenger => "SCAVENGER",
+            GameMode::Hardcore => "HARDCORE"
+        };
+
+        env::var(format!("TABLE_ID_{}_{}", mode, difficulty.env_key())).ok()
+    }
+}
@@ -7,6 +7,11 @@ use ggez::{
 use crate::utils::*;
 use crate::button::Button;
 use crate::text::Text;
+use crate::difficulty::Difficulty;
+use crate::game_mode::GameMode;
+use crate::daily::DailyChallenge;
+use crate::storage::DailyRecord;
+use crate::ui::Ui;
 use std::collections::HashMap;
 use serde::Deserialize;
 use serde_json::Value;
@@ -35,18 +40,33 @@ pub struct GameOver {
     buttons: HashMap<String, Button>
 }
 
-const BUTTON_WIDTH: f32 = 200.0;
-const BUTTON_HEIGHT: f32 = 50.0;
+// In UI units, see Ui
+const BUTTON_WIDTH: f32 = 140.0;
+const BUTTON_HEIGHT: f32 = 35.0;
 
 impl GameOver {
-    pub fn new(ctx: &mut Context, cur_score: u32, stats: Stats, screen_size: (f32, f32)) -> GameResult<GameOver> {
-        loading_screen(ctx, screen_size);
+    pub fn new(ctx: &mut Context, cur_score: u32, stats: Stats, difficulty: Difficulty,
+        mode: GameMode, daily: Option<(&DailyChallenge, DailyRecord, Option<String>)>, ui: &Ui) -> GameResult<GameOver>
+    {
+        loading_screen(ctx, ui.get_screen_size());
+        let area = ui.area();
+
+        // Only the first daily challenge of the day goes to the leaderboard
+        let (table_id, no_table) = match daily {
+            Some((challenge, _, _)) if !challenge.is_scored() =>
+                (None, "Essai non classé, seul le premier défi du jour compte"),
+            Some(_) => (DailyChallenge::table_id(), "Pas de classement pour le défi du jour"),
+            None => (mode.table_id(difficulty), "Pas de classement pour ce mode et cette difficulté")
+        };
 
         let leaderboard; let stats_status;
         match get_credentials() {
             Ok(cred) => {
-                leaderboard = set_leaderboard(cur_score, cred.clone());
-                stats_status = record_stats(stats, cred.clone());
+                leaderboard = match table_id {
+                    Some(table_id) => set_leaderboard(cur_score, &table_id, cred.clone()),
+                    None => String::from(no_table)
+                };
+                stats_status = record_stats(stats, mode.stats_prefix(), cred.clone());
             },
             Err(e) => {
                 leaderboard = e.clone();
@@ -56,26 +76,41 @@ impl GameOver {
 
         let font_path = "/Fonts/arial_narrow_7.ttf".to_string();
 
-        let mut game_over_text = Text::new(ctx, String::from("Game Over"), font_path.clone(), 150.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
-        game_over_text.set_pos(Point2::new(screen_size.0 / 2.0 - game_over_text.width(ctx) / 2.0,
-                screen_size.1 / 5.0 - game_over_text.height(ctx) / 2.0));
-
-        let mut leaderboard_text = Text::new(ctx, String::from(leaderboard), font_path.clone(), 50.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
-        leaderboard_text.set_pos(Point2::new(screen_size.0 / 2.0 - leaderboard_text.width(ctx) / 2.0,
-                screen_size.1 / 2.4 - game_over_text.height(ctx) / 2.0));
-
-        let mut score_text = Text::new(ctx, String::from(format!("Score : {}\n{}", cur_score, stats_status)),
-            font_path.clone(), 30.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
-        score_text.set_pos(Point2::new(screen_size.0 / 2.0 - score_text.width(ctx) / 2.0,
-                screen_size.1 / 1.3 - score_text.height(ctx) / 2.0));
+        // Running out of time in Time Attack isn't really a Game Over
+        let time_up = mode.time_limit().is_some_and(|limit| stats.time_played as f64 >= limit);
+        let title = if time_up { "Temps écoulé" } else { "Game Over" };
+        let mut game_over_text = Text::new(ctx, String::from(title), font_path.clone(), 100.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
+        game_over_text.set_pos(Point2::new(area.w / 2.0 - game_over_text.width(ctx) / 2.0,
+                area.h / 5.0 - game_over_text.height(ctx) / 2.0));
+
+        let mut leaderboard_text = Text::new(ctx, String::from(leaderboard), font_path.clone(), 34.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
+        leaderboard_text.set_pos(Point2::new(area.w / 2.0 - leaderboard_text.width(ctx) / 2.0,
+                area.h / 2.4 - game_over_text.height(ctx) / 2.0));
+
+        let game_infos = match &daily {
+            Some((challenge, record, save_error)) => {
+                let infos = format!("Défi du jour du {}\nMeilleur score du jour : {} (vague {})",
+                    challenge.get_date(), record.best_score, record.best_wave);
+                match save_error {
+                    Some(e) => format!("{}\nErreur sauvegarde : {}", infos, e),
+                    None => infos
+                }
+            },
+            None => format!("Mode : {}\nDifficulté : {}", mode.name(), difficulty.name())
+        };
+        let mut score_text = Text::new(ctx, format!("Score : {}\n{}\n{}",
+            cur_score, game_infos, stats_status),
+            font_path.clone(), 20.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
+        score_text.set_pos(Point2::new(area.w / 2.0 - score_text.width(ctx) / 2.0,
+                area.h / 1.3 - score_text.height(ctx) / 2.0));
 
         let color_not_hover = Color::from_rgb(255, 255, 255);
         let color_hover = Color::from_rgb(160, 160, 160);
 
-        let menu_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, screen_size.0 / 4.0 - BUTTON_WIDTH / 2.0,
-            screen_size.1 / 1.1 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 5.0, "Menu".to_string())?;
-        let replay_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, (3.0 * screen_size.0) / 4.0 - BUTTON_WIDTH / 2.0,
-            screen_size.1 / 1.1 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 5.0, "Rejouer".to_string())?;
+        let menu_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, area.w / 4.0 - BUTTON_WIDTH / 2.0,
+            area.h / 1.1 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 3.0, "Menu".to_string())?;
+        let replay_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, (3.0 * area.w) / 4.0 - BUTTON_WIDTH / 2.0,
+            area.h / 1.1 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 3.0, "Rejouer".to_string())?;
 
         let mut buttons = HashMap::new();
         buttons.insert("menu".to_string(), menu_button);
@@ -99,6 +134,7 @@ impl GameOver {
 
     }
 
+    // On the UI canvas, between Ui::begin and Ui::end
     pub fn draw(&self, ctx: &mut Context) -> GameResult {
         for (_, text) in self.texts.iter() {
             text.draw(ctx)?;
@@ -111,6 +147,7 @@ impl GameOver {
         Ok(())
     }
 
+    // The mouse pos is where it is on the UI canvas, see Ui::screen_to_ui
     pub fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32) {
         for (_, button) in self.buttons.iter_mut() {
             button.mouse_motion_event(ctx, x, y);
@@ -132,7 +169,8 @@ impl GameOver {
         0
     }
 
-    pub fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+    pub fn resize_event(&mut self, ctx: &mut Context, ui: &Ui) {
+        let (width, height) = (ui.area().w, ui.area().h);
         let game_over_text = self.texts.get_mut(&"game_over".to_string()).unwrap();
         let (t_w, t_h) = (game_over_text.width(ctx), game_over_text.height(ctx));
         game_over_text.set_pos(Point2::new(width / 2.0 - t_w / 2.0,
@@ -156,16 +194,16 @@ impl GameOver {
     }
 }
 
-fn record_stats(stats: Stats, cred: Credentials) -> String {
+// The prefix keeps the stats of each game mode apart
+fn record_stats(stats: Stats, prefix: &str, cred: Credentials) -> String {
     // Check if the user has stats in the GameJolt API, if not create it
     let api_url = "https://api.gamejolt.com/api/game/v1_2/data-store/?";
 
-    let keys: [&str; 6] = ["bertrand_killed", "shots", "powerups_activated",
-                           "hits_taken", "time_played", "games_played"];
+    let keys = STATS_KEYS;
 
     for key in keys.iter() {
-        let mut url = format!("{}game_id={}&key={}&username={}&user_token={}",
-                            api_url, cred.game_id, key, cred.username, cred.user_token); 
+        let mut url = format!("{}game_id={}&key={}{}&username={}&user_token={}",
+                            api_url, cred.game_id, prefix, key, cred.username, cred.user_token); 
         let mut hasher = sha1::Sha1::new();
         hasher.update(format!("{}{}", url, cred.private_key).as_bytes());
         let signature = hasher.digest().to_string();
@@ -187,8 +225,8 @@ fn record_stats(stats: Stats, cred: Credentials) -> String {
         }
         if res["response"]["success"] == "false" {
             let mut url = "https://api.gamejolt.com/api/game/v1_2/data-store/set/?".to_string();
-            url = format!("{}game_id={}&key={}&data=0&username={}&user_token={}",
-                            url, cred.game_id, key, cred.username, cred.user_token);
+            url = format!("{}game_id={}&key={}{}&data=0&username={}&user_token={}",
+                            url, cred.game_id, prefix, key, cred.username, cred.user_token);
             let mut hasher = sha1::Sha1::new();
             hasher.update(format!("{}{}", url, cred.private_key).as_bytes());
             let signature = hasher.digest().to_string();
@@ -212,18 +250,23 @@ fn record_stats(stats: Stats, cred: Credentials) -> String {
             "powerups_activated" => stats.powerups_activated,
             "shots" => stats.shots,
             "time_played" => stats.time_played,
+            "hits_absorbed" => stats.hits_absorbed,
+            "time_freezes" => stats.time_freezes,
+            "multishots" => stats.multishots,
+            "magnets" => stats.magnets,
+            "explosion_kills" => stats.explosion_kills,
             _ => unreachable!()
         };
-        let mut global_url = format!("/data-store/update/?game_id={}&key={}&operation=add&value={}",
-                                    cred.game_id, key, value);
+        let mut global_url = format!("/data-store/update/?game_id={}&key={}{}&operation=add&value={}",
+                                    cred.game_id, prefix, key, value);
         let mut hasher = sha1::Sha1::new();
         hasher.update(format!("{}{}", global_url, cred.private_key).as_bytes());
         let signature = hasher.digest().to_string();
         global_url = format!("{}&signature={}", global_url, signature);
         global_url = encode(&global_url); 
 
-        let mut user_url = format!("/data-store/update/?game_id={}&key={}&username={}&user_token={}&operation=add&value={}",
-                                    cred.game_id, key, cred.username, cred.user_token, value);
+        let mut user_url = format!("/data-store/update/?game_id={}&key={}{}&username={}&user_token={}&operation=add&value={}",
+                                    cred.game_id, prefix, key, cred.username, cred.user_token, value);
         let mut hasher = sha1::Sha1::new();
         hasher.update(format!("{}{}", user_url, cred.private_key).as_bytes());
         let signature = hasher.digest().to_string();
@@ -258,10 +301,9 @@ fn record_stats(stats: Stats, cred: Credentials) -> String {
     String::from("Vos statistiqes ont été enregistrées")
 }
 
-fn set_leaderboard(cur_score: u32, cred: Credentials) -> String {
+fn set_leaderboard(cur_score: u32, table_id: &str, cred: Credentials) -> String {
 
     let mut api_url = "https://api.gamejolt.com/api/game/v1_2/scores/add/?".to_string();
-    let table_id = "594910";
     let mut leaderboard = String::from("");
     let mut hasher = sha1::Sha1::new();
 
@@ -278,7 +320,8 @@ fn set_leaderboard(cur_score: u32, cred: Credentials) -> String {
 
     // Get the highest scores from the GameJolt API
     hasher = sha1::Sha1::new();
-    let mut api_url = "https://api.gamejolt.com/api/game/v1_2/scores/?game_id=587107&limit=5&table_id=594910".to_string();
+    let mut api_url = format!("https://api.gamejolt.com/api/game/v1_2/scores/?game_id={}&limit=5&table_id={}",
+                            cred.game_id, table_id);
     hasher.update(format!("{}{}", api_url, cred.private_key).as_bytes());
     let signature = hasher.digest().to_string();
     api_url = format!("{}&signature={}", api_url, signature);
@@ -0,0 +1,224 @@
+use ggez::{
+    graphics, Context, GameResult,
+    graphics::{ Color, Rect, BlendMode },
+    nalgebra::{ Point2, Vector2 }
+};
+
+// A whole day (and night) lasts that many waves, starting at noon
+const DAY_CYCLE_WAVES: f32 = 4.0;
+// Even at midnight a bit of the map can be seen
+const MAX_DARKNESS: f32 = 0.88;
+// Past this much darkness it counts as night
+const NIGHT_THRESHOLD: f32 = 0.5;
+// How far Fabien's light goes, in world units
+const LIGHT_RADIUS: f32 = 70.0;
+// A gunshot lights up that much more around it, for that long
+const FLASH_RADIUS: f32 = 60.0;
+const FLASH_TIME: f32 = 0.12;
+const GLOW_RADIUS: f32 = 14.0;
+// Size of the generated gradients, in pixels
+const GRADIENT_SIZE: u16 = 128;
+
+// How dark it is (0 at noon, MAX_DARKNESS at midnight), from how far into
+// the waves the game is. progress is how much of the current wave went by.
+pub fn darkness(wave: u32, progress: f32) -> f32 {
+    let cycle = (wave.saturating_sub(1) as f32 + progress.clamp(0.0, 1.0)) / DAY_CYCLE_WAVES;
+    (1.0 - (cycle * 2.0 * std::f32::consts::PI).cos()) / 2.0 * MAX_DARKNESS
+}
+
+pub fn is_night(darkness: f32) -> bool {
+    darkness > NIGHT_THRESHOLD
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum LightingQuality {
+    // Darkness with Fabien's light, the muzzle flashes and the glowing powerups
+    Full,
+    // Only darker edges, for the computers that struggle
+    Simple
+}
+
+impl LightingQuality {
+    pub fn next(&self) -> LightingQuality {
+        match self {
+            LightingQuality::Full => LightingQuality::Simple,
+            LightingQuality::Simple => LightingQuality::Full
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LightingQuality::Full => "Complet",
+            LightingQuality::Simple => "Simple"
+        }
+    }
+}
+
+// A square image, white with the alpha going from center to edges as given
+fn gradient(ctx: &mut Context, alpha: impl Fn(f32) -> f32) -> GameResult<graphics::Image> {
+    let size = GRADIENT_SIZE as usize;
+    let mut pixels = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let dx = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            let dy = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+            let a = alpha((dx * dx + dy * dy).sqrt().min(1.0)).clamp(0.0, 1.0);
+            pixels.extend_from_slice(&[255, 255, 255, (a * 255.0) as u8]);
+        }
+    }
+
+    // It's scaled up a lot, it has to stay smooth
+    let mut image = graphics::Image::from_rgba8(ctx, GRADIENT_SIZE, GRADIENT_SIZE, &pixels)?;
+    image.set_filter(graphics::FilterMode::Linear);
+
+    Ok(image)
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Draws the night over the world, with holes where there's light
+pub struct Lighting {
+    quality: LightingQuality,
+    darkness: f32,
+    // Transparent in the middle, opaque on the edges: the darkness around a light
+    shadow: graphics::Image,
+    // The other way around, added on top for the flashes and the glows
+    glow: graphics::Image,
+    // Where the last gunshots were, and how long they still light up
+    flashes: Vec<(Point2<f32>, f32)>
+}
+
+impl Lighting {
+    pub fn new(ctx: &mut Context, quality: LightingQuality) -> GameResult<Lighting> {
+        let lighting = Lighting {
+            quality: quality,
+            darkness: 0.0,
+            shadow: gradient(ctx, smoothstep)?,
+            glow: gradient(ctx, |d| (1.0 - d).powi(2))?,
+            flashes: vec![]
+        };
+
+        Ok(lighting)
+    }
+
+    pub fn set_quality(&mut self, quality: LightingQuality) {
+        self.quality = quality;
+    }
+
+    pub fn get_darkness(&self) -> f32 {
+        self.darkness
+    }
+
+    pub fn update(&mut self, dt: f32, darkness: f32) {
+        self.darkness = darkness;
+        for (_, time) in self.flashes.iter_mut() { *time -= dt; }
+        self.flashes.retain(|(_, time)| *time > 0.0);
+    }
+
+    pub fn flash(&mut self, pos: Point2<f32>) {
+        self.flashes.push((pos, FLASH_TIME));
+    }
+
+    pub fn clear(&mut self) {
+        self.darkness = 0.0;
+        self.flashes.clear();
+    }
+
+    // Over the world, before the HUD. Fabien is where the light is.
+    pub fn draw(&self, ctx: &mut Context, camera: Rect, fabien: Point2<f32>, powerups: &[Rect]) -> GameResult {
+        if self.darkness <= 0.01 { return Ok(()); }
+
+        match self.quality {
+            LightingQuality::Simple => {
+                let tint = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), camera,
+                    Color::new(0.0, 0.0, 0.1, self.darkness * 0.6))?;
+                graphics::draw(ctx, &tint, graphics::DrawParam::default())?;
+                self.draw_shadow(ctx, camera, Point2::new(camera.x + camera.w / 2.0, camera.y + camera.h / 2.0),
+                    camera.w.max(camera.h) / 2.0, self.darkness * 0.5)
+            },
+            LightingQuality::Full => {
+                // The brightest flash makes Fabien's light bigger for a moment
+                let flash = self.flashes.iter().map(|(_, time)| time / FLASH_TIME).fold(0.0, f32::max);
+                let radius = LIGHT_RADIUS + FLASH_RADIUS * flash;
+                self.draw_shadow(ctx, camera, fabien, radius, self.darkness)?;
+
+                graphics::set_blend_mode(ctx, BlendMode::Add)?;
+                for (pos, time) in self.flashes.iter() {
+                    self.draw_glow(ctx, *pos, FLASH_RADIUS, Color::new(1.0, 0.85, 0.5, time / FLASH_TIME * 0.6))?;
+                }
+                for powerup in powerups.iter() {
+                    let center = Point2::new(powerup.x + powerup.w / 2.0, powerup.y + powerup.h / 2.0);
+                    self.draw_glow(ctx, center, GLOW_RADIUS, Color::new(1.0, 0.9, 0.4, self.darkness * 0.5))?;
+                }
+                graphics::set_blend_mode(ctx, BlendMode::Alpha)
+            }
+        }
+    }
+
+    // Dark all around the circle, and fading in towards its middle
+    fn draw_shadow(&self, ctx: &mut Context, camera: Rect, center: Point2<f32>, radius: f32, alpha: f32) -> GameResult {
+        let color = Color::new(0.0, 0.0, 0.05, alpha);
+        let hole = Rect::new(center.x - radius, center.y - radius, radius * 2.0, radius * 2.0);
+        let scale = radius * 2.0 / GRADIENT_SIZE as f32;
+        graphics::draw(ctx, &self.shadow, graphics::DrawParam::new()
+            .dest(Point2::new(hole.x, hole.y))
+            .scale(Vector2::new(scale, scale))
+            .color(color))?;
+
+        // Everything around the hole, as four bands
+        let (left, top) = (camera.x.min(hole.x), camera.y.min(hole.y));
+        let (right, bottom) = ((camera.x + camera.w).max(hole.right()), (camera.y + camera.h).max(hole.bottom()));
+        let bands = [
+            Rect::new(left, top, right - left, hole.y - top),
+            Rect::new(left, hole.bottom(), right - left, bottom - hole.bottom()),
+            Rect::new(left, hole.y, hole.x - left, hole.h),
+            Rect::new(hole.right(), hole.y, right - hole.right(), hole.h)
+        ];
+        // The hole can be as big as the view
+        if !bands.iter().any(|band| band.w > 0.0 && band.h > 0.0) { return Ok(()); }
+
+        let mut mesh = graphics::MeshBuilder::new();
+        for band in bands.iter().filter(|band| band.w > 0.0 && band.h > 0.0) {
+            mesh.rectangle(graphics::DrawMode::fill(), *band, color);
+        }
+        let mesh = mesh.build(ctx)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+    }
+
+    fn draw_glow(&self, ctx: &mut Context, center: Point2<f32>, radius: f32, color: Color) -> GameResult {
+        let scale = radius * 2.0 / GRADIENT_SIZE as f32;
+        graphics::draw(ctx, &self.glow, graphics::DrawParam::new()
+            .dest(Point2::new(center.x - radius, center.y - radius))
+            .scale(Vector2::new(scale, scale))
+            .color(color))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_day_lasts_a_few_waves() {
+        // Noon at the start, midnight halfway through, noon again after that
+        assert_eq!(darkness(1, 0.0), 0.0);
+        assert!((darkness(1 + DAY_CYCLE_WAVES as u32 / 2, 0.0) - MAX_DARKNESS).abs() < 1e-5);
+        assert!(darkness(1 + DAY_CYCLE_WAVES as u32, 0.0) < 1e-5);
+        assert!(darkness(2, 0.0) > darkness(1, 0.5));
+    }
+
+    #[test]
+    fn progress_stays_within_the_wave() {
+        assert_eq!(darkness(2, 1.5), darkness(2, 1.0));
+        assert_eq!(darkness(2, -1.0), darkness(2, 0.0));
+        assert_eq!(darkness(0, 0.0), darkness(1, 0.0));
+    }
+
+    #[test]
+    fn night_is_when_it_gets_dark() {
+        assert!(!is_night(darkness(1, 0.0)));
+        assert!(is_night(darkness(1 + DAY_CYCLE_WAVES as u32 / 2, 0.0)));
+    }
+}
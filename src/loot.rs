@@ -0,0 +1,88 @@
+use rand::Rng;
+use serde::Deserialize;
+use crate::powerup::{ PowerupCatalogue, EffectDef };
+use crate::utils::game_rng;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LootDrop {
+    // Id of the powerup in powerups.json
+    pub id: String,
+    // Chance (between 0 and 1) of this drop when a Bertrand dies
+    pub chance: f32
+}
+
+// What a Bertrand can leave behind him when he dies, as written in the loot
+// section of powerups.json (one table per kind of Bertrand)
+#[derive(Deserialize, Clone, Debug)]
+pub struct LootTable {
+    pub drops: Vec<LootDrop>,
+    // Under this number of ammos, the chance of dropping ammos goes up...
+    pub pity_threshold: u32,
+    // ...by this much for each missing ammo
+    pub pity_per_ammo: f32
+}
+
+impl LootTable {
+    // Every drop has to be in the catalogue, and the chances between 0 and 1
+    pub fn validate(&self, catalogue: &PowerupCatalogue) -> Result<(), String> {
+        let is_chance = |chance: f32| (0.0..=1.0).contains(&chance);
+        for drop in self.drops.iter() {
+            if catalogue.get(&drop.id).is_none() { return Err(format!("{} : no such powerup", drop.id)); }
+            if !is_chance(drop.chance) { return Err(format!("{} : the chance has to be between 0 and 1", drop.id)); }
+        }
+        if !is_chance(self.pity_per_ammo) {
+            return Err(String::from("pity_per_ammo : has to be between 0 and 1"));
+        }
+
+        Ok(())
+    }
+
+    // Roll the table once and give the id of what's dropped, Fabien's ammos
+    // are needed for the pity
+    pub fn roll(&self, catalogue: &PowerupCatalogue, nb_ammos: u32) -> Option<&str> {
+        let roll = game_rng(|rng| rng.gen::<f32>());
+
+        let mut cumulated_chance = 0.0;
+        for drop in self.drops.iter() {
+            cumulated_chance += self.chance_of(catalogue, drop, nb_ammos);
+            if roll < cumulated_chance { return Some(&drop.id); }
+        }
+
+        None
+    }
+
+    fn chance_of(&self, catalogue: &PowerupCatalogue, drop: &LootDrop, nb_ammos: u32) -> f32 {
+        let is_ammo = catalogue.get(&drop.id)
+            .is_some_and(|def| matches!(def.effect, EffectDef::AmmoRestock { .. }));
+        if is_ammo && nb_ammos < self.pity_threshold {
+            drop.chance + (self.pity_threshold - nb_ammos) as f32 * self.pity_per_ammo
+        } else { drop.chance }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bertrand::BertrandKind;
+
+    fn catalogue_with(loot: &str) -> Result<PowerupCatalogue, String> {
+        let json = format!(r#"{{ "powerups": [ {{ "id": "ammo_drop", "sprite": "/bullet.png", "scale": 0.6,
+            "effect": {{ "type": "AmmoRestock", "amount": [3, 6] }} }} ], "loot": {} }}"#, loot);
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn loot_tables_come_from_the_catalogue() {
+        let catalogue = catalogue_with(r#"{ "Regular": { "drops": [ { "id": "ammo_drop", "chance": 0.12 } ],
+            "pity_threshold": 10, "pity_per_ammo": 0.04 } }"#).unwrap();
+        let table = catalogue.loot(BertrandKind::Regular).unwrap();
+        assert!(table.validate(&catalogue).is_ok());
+        // The pity only kicks in under the threshold
+        assert_eq!(table.chance_of(&catalogue, &table.drops[0], 10), 0.12);
+        assert!((table.chance_of(&catalogue, &table.drops[0], 5) - 0.32).abs() < 1e-6);
+
+        let catalogue = catalogue_with(r#"{ "Regular": { "drops": [ { "id": "dragon", "chance": 0.5 } ],
+            "pity_threshold": 0, "pity_per_ammo": 0.0 } }"#).unwrap();
+        assert!(catalogue.loot(BertrandKind::Regular).unwrap().validate(&catalogue).is_err());
+    }
+}
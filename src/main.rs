@@ -1,19 +1,38 @@
 mod map; use map::Map;
-mod fabien; use fabien::Fabien;
-mod bertrand; use bertrand::Bertrand;
+mod forest;
+mod terrain; use terrain::ObstacleKind;
+mod tiled;
+mod editor; use editor::{ Editor, EDITOR_MAP_PATH };
+mod fabien; use fabien::{ Fabien, Hit };
+mod bertrand; use bertrand::{ Bertrand, BertrandKind };
 mod menu; use menu::Menu;
 mod game_over; use game_over::GameOver;
 mod pause; use pause::Pause;
-pub mod powerup; use powerup::Powerup;
+pub mod powerup; use powerup::{ Powerup, Powerups, PowerupCatalogue };
+pub mod loot;
+pub mod decal; use decal::Decals;
+pub mod wave; use wave::{ WaveSchedule, WaveDef, WaveSummary, SpawnPattern };
+pub mod difficulty; use difficulty::Difficulty;
+pub mod map_size; use map_size::MapSize;
+pub mod minimap; use minimap::{ Minimap, MinimapSize };
+pub mod lighting; use lighting::{ Lighting, LightingQuality };
+pub mod render; use render::{ RenderQueue, Drawable };
+pub mod camera; use camera::Camera;
+pub mod ui; use ui::{ Ui, UiScale, Anchor };
+pub mod debug; use debug::{ DebugOverlay, HitboxKind };
+pub mod director; use director::Director;
+pub mod game_mode; use game_mode::GameMode;
+pub mod storage; use storage::Storage;
+pub mod daily; use daily::DailyChallenge;
 pub mod utils; use utils::*;
 pub mod bullet;
-pub mod particle;
+pub mod particle; use particle::Particle;
 pub mod button;
 pub mod text; use text::Text;
 
 extern crate mysql;
 extern crate dotenv; use dotenv::dotenv;
-extern crate rand;
+extern crate rand; use rand::Rng;
 extern crate serde_json;
 extern crate serde;
 extern crate reqwest;
@@ -28,39 +47,93 @@ use ggez::{
     input::mouse::MouseButton
 };
 
+// Maximum number of blood splats on the ground, and how long they stay there
+const MAX_DECALS: usize = 300;
+const DECAL_LIFETIME: f32 = 60.0;
+// At full darkness Bertrands come that much more often
+const NIGHT_SPAWN_BONUS: f32 = 0.3;
+// How much the camera shakes when Fabien gets hit or something blows up, see Camera::add_trauma
+const HIT_TRAUMA: f32 = 0.5;
+const EXPLOSION_TRAUMA: f32 = 0.3;
+
 enum GameState {
     Menu,
     Playing,
     GameOver,
     Pause,
+    Editor
 }
 
 struct MainState {
     screen_size: (f32, f32), 
     map_size: (f32, f32),
     fullscreen: bool,
+    difficulty: Difficulty,
+    mode: GameMode,
+    use_director: bool,
+    director: Director,
+    storage: Storage,
+    // Some when playing the daily challenge
+    daily: Option<DailyChallenge>,
+    // When it couldn't be saved, it's told at the game over
+    daily_save_error: Option<String>,
     stats: Stats,
     game_state: GameState,
     menu: Menu,
     game_over: Option<GameOver>,
     pause: Option<Pause>,
+    editor: Option<Editor>,
     map: Map,
+    // The hand-made map that's loaded, None when it's a random one
+    map_path: Option<String>,
+    // How big the random map is, the hand-made ones have their own size
+    map_preset: MapSize,
+    minimap: Minimap,
+    lighting: Lighting,
+    camera: Camera,
+    ui: Ui,
+    debug: DebugOverlay,
     fabien: Fabien,
     bertrands: Vec<Bertrand>,
     powerups: Vec<Powerup>,
+    powerup_catalogue: PowerupCatalogue,
+    decals: Decals,
+    // Explosions and such, that don't belong to anyone
+    particles: Vec<Particle>,
     sec_since_last_bertrand: f64,
     sec_since_last_powerup: f64,
     time_passed: f64,
     updated_this_frame: bool,
-    wave: u32
+    waves: WaveSchedule,
+    wave: u32,
+    // The current wave's definition, the endless ones are computed so it's kept here
+    wave_def: WaveDef,
+    wave_time: f64,
+    wave_spawned: u32,
+    wave_kills: u32,
+    wave_start_score: u32,
+    // Time left before the next wave starts, nothing spawns in the meantime
+    intermission: f64,
+    last_wave: Option<WaveSummary>,
+    cluster_center: (f32, f32)
 }
 
 impl MainState {
     fn new(ctx: &mut Context, width: f32, height: f32) -> GameResult<MainState> {
-        let map_size = (2000.0, 2000.0);
-        let mut map = Map::new(ctx, map_size.0, map_size.1)?;
-        let fabien = Fabien::new(ctx, map.get_trees(), map_size, (width, height))?;
-        let menu = Menu::new(ctx, (width, height))?;
+        let map_preset = MapSize::Medium;
+        let map = Map::new(ctx, map_preset)?;
+        let map_size = (map.get_width(), map.get_height());
+        let minimap = Minimap::new(ctx, &map, MinimapSize::Medium)?;
+        let lighting = Lighting::new(ctx, LightingQuality::Full)?;
+        let fabien = Fabien::new(ctx, map.get_spawn(), map_size)?;
+        let camera = Camera::new((width, height), map_size);
+        let ui = Ui::new((width, height), UiScale::Normal);
+        let mut menu = Menu::new(ctx, (ui.area().w, ui.area().h))?;
+        let storage = Storage::load(ctx);
+        menu.set_daily_played(ctx, !DailyChallenge::today(&storage).is_scored());
+        let decals = Decals::new(ctx, MAX_DECALS, DECAL_LIFETIME)?;
+        let waves = WaveSchedule::load(ctx, "/waves.json")?;
+        let powerup_catalogue = PowerupCatalogue::load(ctx, "/powerups.json")?;
         let game_over = None;
 
         ggez::graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width, height))?;
@@ -76,20 +149,47 @@ impl MainState {
             screen_size: (width, height),
             map_size: map_size,
             fullscreen: true,
-            stats: Stats { bertrand_killed: 0, shots: 0, powerups_activated: 0, hits_taken: 0, time_played: 0 },
+            difficulty: Difficulty::Normal,
+            mode: GameMode::Survival,
+            use_director: false,
+            director: Director::new(),
+            storage: storage,
+            daily: None,
+            daily_save_error: None,
+            stats: Stats::default(),
             game_state: GameState::Menu,
             menu: menu,
             game_over: game_over,
             pause: None,
+            editor: None,
             map: map,
+            map_path: None,
+            map_preset: map_preset,
+            minimap: minimap,
+            lighting: lighting,
+            camera: camera,
+            ui: ui,
+            debug: DebugOverlay::new(),
             fabien: fabien,
             bertrands: Vec::<Bertrand>::new(),
             powerups: Vec::<Powerup>::new(),
+            powerup_catalogue: powerup_catalogue,
+            decals: decals,
+            particles: vec![],
             sec_since_last_bertrand: 0.0,
             sec_since_last_powerup: 0.0,
             time_passed: 0.0,
             updated_this_frame: true,
-            wave: 1
+            wave_def: waves.get(1),
+            waves: waves,
+            wave: 1,
+            wave_time: 0.0,
+            wave_spawned: 0,
+            wave_kills: 0,
+            wave_start_score: 0,
+            intermission: 0.0,
+            last_wave: None,
+            cluster_center: (map_size.0 / 4.0, map_size.1 / 4.0)
         };
         Ok(s)
     }
@@ -101,19 +201,27 @@ impl MainState {
         // animation_frames: 0,
         // and let the bullet go through if Fabien has the powerup for that
         let mut nb_removed = 0;
+        let mut killed: Vec<(BertrandKind, Rect)> = vec![];
+        let mut explosions: Vec<(Point2<f32>, f32)> = vec![];
         {
             use std::collections::VecDeque;
             use crate::bullet::Bullet;
 
             let shots: &mut VecDeque<Bullet> = self.fabien.get_shots(); 
             for bullet in shots.iter_mut() {
-                self.bertrands.retain(|bertrand| { 
+                for bertrand in self.bertrands.iter_mut() {
+                    if bertrand.is_dying() { continue; }
                     if bertrand.get_hitbox().overlaps(&bullet.get_hitbox()) {
                         nb_removed += 1;
+                        killed.push((bertrand.get_kind(), bertrand.get_hitbox()));
+                        bertrand.kill();
                         bullet.hit_something();
-                        false 
-                    } else { true }
-                });
+                        if let Some(radius) = bullet.get_explosion_radius() {
+                            let hitbox = bullet.get_hitbox();
+                            explosions.push((Point2::new(hitbox.x, hitbox.y), radius));
+                        }
+                    }
+                }
 
                 if bullet.get_nb_pierce() < 0 { 
                     bullet.set_life(0.0);
@@ -121,31 +229,105 @@ impl MainState {
                 }
             }
         }
+
+        // Trees and rocks stop the bullets (they fly over the rest), and get
+        // chipped a bit more each time until they break
+        let mut chips: Vec<(ObstacleKind, Point2<f32>)> = vec![];
+        {
+            let map = &mut self.map;
+            self.fabien.get_shots().retain(|bullet| {
+                if bullet.get_nb_pierce() < 0 { return true; }
+
+                let hitbox = bullet.get_hitbox();
+                match map.obstacle_hit_by(hitbox) {
+                    Some(i) => {
+                        map.damage_obstacle(i);
+                        chips.push((map.get_obstacles()[i].get_kind(), Point2::new(hitbox.x, hitbox.y)));
+                        if let Some(radius) = bullet.get_explosion_radius() {
+                            explosions.push((Point2::new(hitbox.x, hitbox.y), radius));
+                        }
+                        false
+                    },
+                    None => true
+                }
+            });
+        }
+        // Explosive rounds also hurt everyone around where they hit
+        for (center, radius) in explosions.iter() {
+            for bertrand in self.bertrands.iter_mut() {
+                if bertrand.is_dying() { continue; }
+                let hitbox = bertrand.get_hitbox();
+                let bertrand_center = Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h / 2.0);
+                if (bertrand_center - center).norm() <= *radius {
+                    bertrand.kill();
+                    nb_removed += 1;
+                    self.stats.explosion_kills += 1;
+                    killed.push((bertrand.get_kind(), hitbox));
+                }
+            }
+            for i in self.map.obstacles_around(*center, *radius) { self.map.damage_obstacle(i); }
+            self.explode(ctx, *center, *radius)?;
+            self.camera.add_trauma(EXPLOSION_TRAUMA);
+        }
+
+        for (kind, pos) in chips { self.debris(ctx, pos, kind, 5)?; }
+        let destroyed = self.map.remove_destroyed();
+        if !destroyed.is_empty() { self.minimap.rebuild(ctx, &self.map)?; }
+        for obstacle in destroyed {
+            let hitbox = obstacle.get_hitbox();
+            let center = Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h / 2.0);
+            self.debris(ctx, center, obstacle.get_kind(), 40)?;
+            // The leaves of a tree come down with it
+            if obstacle.get_kind().blocks_sight() { self.debris(ctx, center, ObstacleKind::Bush, 30)?; }
+        }
+
         self.fabien.add_to_score(nb_removed);
         self.stats.bertrand_killed += nb_removed as u64;
+        self.wave_kills += nb_removed;
+        if self.use_director { self.director.record_kills(self.time_passed, nb_removed); }
+
+        // Dead Bertrands leave some blood, and sometimes something else, behind them
+        for (kind, hitbox) in killed.iter() {
+            self.decals.add(Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h));
+            let drop = self.powerup_catalogue.loot(*kind)
+                .and_then(|table| table.roll(&self.powerup_catalogue, self.fabien.get_nb_ammos()))
+                .and_then(|id| self.powerup_catalogue.get(id));
+            if let Some(def) = drop.filter(|def| self.mode.allows_drop(&def.effect)) {
+                self.powerups.push(Powerup::from_def(ctx, def,
+                    Point2::new(hitbox.x, hitbox.y + hitbox.h / 2.0))?);
+            }
+        }
 
-        // Check if any Bertrand is colliding with Fabien
-        // I think I cannot use the retain methode because I want to execute
-        // a function on Fabien if they're colliding, and retain won't let me
-        // do that.
-        // self.bertrands.retain(|bertrand| bertrand.get_hitbox().overlaps(&fabien_hitbox));
-        for bertrand in self.bertrands.iter_mut() {
-            if bertrand.is_swinging() { continue; }
-            if bertrand.get_hitbox().overlaps(&fabien_hitbox) {
-                if self.fabien.take_hit() {
-                    self.stats.hits_taken += 1;
+        // Check if any Bertrand is landing a blow on Fabien (not while they're frozen)
+        let frozen = self.fabien.is_time_frozen();
+        for bertrand in self.bertrands.iter_mut().filter(|_| !frozen) {
+            if let Some(attack_hitbox) = bertrand.get_attack_hitbox() {
+                if attack_hitbox.overlaps(&fabien_hitbox) {
+                    bertrand.land_strike();
+                    match self.fabien.take_hit() {
+                        Hit::Taken => {
+                            self.camera.add_trauma(HIT_TRAUMA);
+                            self.stats.hits_taken += 1;
+                            if self.use_director { self.director.record_hit(self.time_passed); }
+                        },
+                        Hit::Absorbed => self.stats.hits_absorbed += 1,
+                        Hit::Ignored => {}
+                    }
                 }
-                bertrand.swing();
-                break;
             }
         }
-        self.bertrands.retain(|b| !b.is_dead());
 
         // Check if Fabien is colliding with a powerup
         let mut to_remove: Option<usize> = None;
         for (i, powerup) in self.powerups.iter().enumerate() {
             if powerup.get_hitbox().overlaps(&fabien_hitbox) {
-                self.fabien.activate_powerup(ctx, powerup.get_powerup())?;
+                match powerup.get_powerup() {
+                    Powerups::TimeFreeze(_) => self.stats.time_freezes += 1,
+                    Powerups::Multishot(_) => self.stats.multishots += 1,
+                    Powerups::Magnet(_) => self.stats.magnets += 1,
+                    _ => {}
+                }
+                self.fabien.activate_powerup(powerup.get_powerup(), powerup.get_sprite());
                 self.stats.powerups_activated += 1;
                 to_remove = Some(i);
                 break;
@@ -153,107 +335,445 @@ impl MainState {
         }
         if let Some(x) = to_remove { self.powerups.remove(x); }
 
-        // Check if a bullet is colliding with a tree
-        let trees = self.map.get_trees();
-        'main: for (i, bullet) in self.fabien.get_shots().iter().enumerate() {
-            for tree in trees.iter() {
-                if bullet.get_hitbox().overlaps(&tree.get_hitbox()) {
-                    to_remove = Some(i); 
-                    break 'main;
-                }
-            }
+        Ok(())
+    }
+
+    // A burst of fire, to see how far the explosion went
+    fn explode(&mut self, ctx: &mut Context, center: Point2<f32>, radius: f32) -> GameResult {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..(radius as usize) {
+            let color = graphics::Color::from_rgb(240, rng.gen_range(80..=200), 30);
+            let angle = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
+            let size = rng.gen::<f32>() * 1.5 + 0.5;
+            let life = rng.gen::<f32>() * 0.5 + 0.3;
+            let rot_speed = if rng.gen::<bool>() { -6.0 } else { 6.0 };
+
+            self.particles.push(Particle::new(center, radius * 2.0, rot_speed, angle, life, color, size, ctx)?);
+        }
+
+        Ok(())
+    }
+
+    // Bits of wood, stone or leaves flying off of something that got shot
+    fn debris(&mut self, ctx: &mut Context, center: Point2<f32>, kind: ObstacleKind, amount: usize) -> GameResult {
+        let mut rng = rand::thread_rng();
+        let (r, g, b) = kind.debris_color();
+
+        for _ in 0..amount {
+            let shade = rng.gen_range(-15..=15);
+            let color = graphics::Color::from_rgb(
+                (r as i16 + shade).clamp(0, 255) as u8,
+                (g as i16 + shade).clamp(0, 255) as u8,
+                (b as i16 + shade).clamp(0, 255) as u8
+            );
+            let angle = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
+            let size = rng.gen::<f32>() * 1.2 + 0.5;
+            let life = rng.gen::<f32>() * 0.4 + 0.2;
+            let speed = rng.gen::<f32>() * 30.0 + 20.0;
+            let rot_speed = if rng.gen::<bool>() { -6.0 } else { 6.0 };
+
+            self.particles.push(Particle::new(center, speed, rot_speed, angle, life, color, size, ctx)?);
         }
-        if let Some(x) = to_remove { self.fabien.get_shots().remove(x); }
 
         Ok(())
     }
 
     fn bertrand_spawning(&mut self, ctx: &mut Context, fps: f64) -> GameResult {
-        // If a minute passed since the last wave change, the wave changes
-        if self.time_passed > (60 * self.wave) as f64 {
-            self.fabien.add_to_score(10 * self.wave);
-            self.wave += 1;
+        // Fabien gets to breathe a bit between two waves
+        if self.intermission > 0.0 {
+            self.intermission -= 1.0 / fps;
+            return Ok(());
         }
 
-        let bertrand_spawning_rate: f32 = 650.0 / (0.9 * self.wave as f32);
+        // If the wave lasted long enough (and was cleared if it has to be), the next one comes
+        self.wave_time += 1.0 / fps;
+        let spawning_over = self.wave_time > self.wave_def.duration || self.wave_spawned >= self.wave_def.spawn_budget;
+        let wave_over = if self.wave_def.clear_to_advance {
+            spawning_over && self.bertrands_left() == 0
+        } else {
+            self.wave_time > self.wave_def.duration
+        };
+        if wave_over {
+            self.end_wave();
+            return Ok(());
+        }
+
+        if spawning_over { return Ok(()); }
+
         self.sec_since_last_bertrand += 1.0 / fps;
+        let spawn_rate = self.spawn_rate();
 
-        let rand_nb = rand(bertrand_spawning_rate) as f64;
+        // The director decides when Bertrands come if it's there, otherwise it's random
+        let spawn = if self.use_director {
+            self.director.should_spawn_bertrand(1.0 / fps as f32, fps, spawn_rate)
+        } else {
+            let rand_nb = rand(spawn_rate) as f64;
+            rand_nb - self.sec_since_last_bertrand < 0.0
+        };
 
-        if rand_nb - self.sec_since_last_bertrand < 0.0 {
+        if spawn {
             self.sec_since_last_bertrand = 0.0;
-            let mut new_bertrand_pos: (f32, f32);
-
-            loop {
-                new_bertrand_pos = (rand(self.map.get_width()), rand(self.map.get_height()));
-                let mut not_in_tree = true;
-                for tree in self.map.get_trees().iter() {
-                    if tree.get_hitbox().contains(
-                        ggez::mint::Point2 {
-                            x: new_bertrand_pos.0, y: new_bertrand_pos.1 })
-                    {
-                        not_in_tree = false;
-                        break;
-                    }
-                }
-                if (new_bertrand_pos.0 < self.fabien.get_hitbox().x - 200.0 ||
-                   new_bertrand_pos.0 > self.fabien.get_hitbox().x + 200.0) &&
-                   (new_bertrand_pos.1 < self.fabien.get_hitbox().y - 200.0 ||
-                   new_bertrand_pos.1 > self.fabien.get_hitbox().y + 200.0) &&
-                   not_in_tree { break; }
-            }
-            self.bertrands.push(Bertrand::new(ctx, Rect::new(
+            self.wave_spawned += 1;
+
+            let new_bertrand_pos = self.spawn_position(self.wave_def.spawn_pattern);
+            let fabien_hitbox = self.fabien.get_hitbox();
+            self.bertrands.push(Bertrand::new(ctx, self.wave_def.pick_kind(), Rect::new(
                 new_bertrand_pos.0, new_bertrand_pos.1, 8.0, 16.0
-            ))?);
+            ), self.difficulty.bertrand_speed_factor(), self.wave, Point2::new(fabien_hitbox.x, fabien_hitbox.y))?);
         }
 
         Ok(())
     }
 
+    // How fast Bertrands come during that wave, right now
+    fn spawn_rate(&self) -> f32 {
+        self.wave_def.spawn_rate * self.difficulty.spawn_rate_factor()
+            * (1.0 - NIGHT_SPAWN_BONUS * self.lighting.get_darkness())
+    }
+
+    fn end_wave(&mut self) {
+        self.fabien.add_to_score(self.wave_def.bonus);
+        self.last_wave = Some(WaveSummary {
+            wave: self.wave,
+            kills: self.wave_kills,
+            score: self.fabien.get_score() - self.wave_start_score,
+            bonus: self.wave_def.bonus
+        });
+
+        self.wave += 1;
+        self.wave_def = self.waves.get(self.wave);
+        self.wave_time = 0.0;
+        self.wave_spawned = 0;
+        self.wave_kills = 0;
+        self.wave_start_score = self.fabien.get_score();
+        self.intermission = self.waves.intermission;
+        self.cluster_center = self.spawn_position(SpawnPattern::Random);
+    }
+
+    // Only the ones of the current wave, the stragglers from the last ones
+    // can be anywhere on the map
+    fn bertrands_left(&self) -> usize {
+        self.bertrands.iter().filter(|b| !b.is_dying() && b.get_wave() == self.wave).count()
+    }
+
+    // Find a spot for a new Bertrand that's not in a tree (or a rock, or a pond) and not too close to Fabien
+    fn spawn_position(&mut self, pattern: SpawnPattern) -> (f32, f32) {
+        let fabien_hitbox = self.fabien.get_hitbox();
+        let (width, height) = (self.map.get_width(), self.map.get_height());
+
+        // Hand-made maps say where the Bertrands come from, the furthest
+        // ones from Fabien are used
+        let spawns = self.map.get_bertrand_spawns();
+        if !spawns.is_empty() {
+            let far_spawns: Vec<&Point2<f32>> = spawns.iter()
+                .filter(|spawn| (spawn.x - fabien_hitbox.x).abs() > 200.0 || (spawn.y - fabien_hitbox.y).abs() > 200.0)
+                .collect();
+            let candidates = if far_spawns.is_empty() { spawns.iter().collect() } else { far_spawns };
+            let spawn = candidates[game_rng(|rng| rng.gen_range(0..candidates.len()))];
+            return (spawn.x + rand(20.0) - 10.0, spawn.y + rand(20.0) - 10.0);
+        }
+
+        for _ in 0..100 {
+            let new_bertrand_pos = match pattern {
+                SpawnPattern::Random => (rand(width), rand(height)),
+                SpawnPattern::Ring => {
+                    let angle = rand(2.0 * std::f32::consts::PI);
+                    let dist = 250.0 + rand(50.0);
+                    (fabien_hitbox.x + angle.cos() * dist, fabien_hitbox.y + angle.sin() * dist)
+                },
+                SpawnPattern::Cluster => (self.cluster_center.0 + rand(120.0) - 60.0,
+                                          self.cluster_center.1 + rand(120.0) - 60.0),
+                SpawnPattern::Edges => match game_rng(|rng| rng.gen_range(0..4)) {
+                    0 => (rand(width), 0.0),
+                    1 => (rand(width), height - 16.0),
+                    2 => (0.0, rand(height)),
+                    _ => (width - 8.0, rand(height))
+                }
+            };
+
+            if new_bertrand_pos.0 < 0.0 || new_bertrand_pos.0 > width ||
+               new_bertrand_pos.1 < 0.0 || new_bertrand_pos.1 > height { continue; }
+
+            let not_in_tree = !self.map.blocks_walking(Rect::new(new_bertrand_pos.0, new_bertrand_pos.1, 8.0, 16.0));
+            if (new_bertrand_pos.0 < fabien_hitbox.x - 200.0 ||
+               new_bertrand_pos.0 > fabien_hitbox.x + 200.0) &&
+               (new_bertrand_pos.1 < fabien_hitbox.y - 200.0 ||
+               new_bertrand_pos.1 > fabien_hitbox.y + 200.0) &&
+               not_in_tree { return new_bertrand_pos; }
+        }
+
+        // Couldn't find anything with this pattern (Fabien is hiding in a corner
+        // or something), any free spot will do
+        if pattern == SpawnPattern::Random { return (rand(width), rand(height)); }
+        self.spawn_position(SpawnPattern::Random)
+    }
+
+    fn powerup_rate(&self) -> f32 {
+        self.wave_def.powerup_rate * self.difficulty.powerup_rate_factor()
+    }
+
     fn powerup_spawning(&mut self, ctx: &mut Context, fps: f64) -> GameResult {
         self.sec_since_last_powerup += 1.0 / fps;
-        let powerup_spawn_rate: f32 = 3500.0 / (0.6 * (self.wave + 1) as f32);
+        let powerup_spawn_rate = self.powerup_rate();
+
+        if self.use_director {
+            if let Some(plan) = self.director.plan_powerup(self.time_passed, 1.0 / fps as f32, fps, powerup_spawn_rate) {
+                let mode = self.mode;
+                let def = plan.powerup.and_then(|id| self.powerup_catalogue.get(id))
+                    .filter(|def| mode.allows_spawn(&def.effect))
+                    .or_else(|| self.powerup_catalogue.pick(self.wave, |def| mode.allows_spawn(&def.effect)));
+                if let Some(def) = def {
+                    let powerup = if plan.near_fabien {
+                        let pos = self.position_near_fabien();
+                        Some(Powerup::from_def(ctx, def, pos)?)
+                    } else {
+                        Powerup::new(ctx, def, &self.map, self.fabien_center())?
+                    };
+                    self.powerups.extend(powerup);
+                }
+                self.sec_since_last_powerup = 0.0;
+            }
+            return Ok(());
+        }
 
         let rand_nb = rand(powerup_spawn_rate) as f64;
 
         if rand_nb - self.sec_since_last_powerup < 0.0 {
-            self.powerups.push(Powerup::new(ctx, self.map_size)?);
+            // Some powerups are forbidden by the game mode
+            let mode = self.mode;
+            if let Some(def) = self.powerup_catalogue.pick(self.wave, |def| mode.allows_spawn(&def.effect)) {
+                let powerup = Powerup::new(ctx, def, &self.map, self.fabien_center())?;
+                self.powerups.extend(powerup);
+            }
             self.sec_since_last_powerup = 0.0;
         }
 
         Ok(())
     }
 
+    fn fabien_center(&self) -> Point2<f32> {
+        let hitbox = self.fabien.get_hitbox();
+        Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h / 2.0)
+    }
+
+    // Somewhere Fabien can get to quickly, but not right under his feet
+    fn position_near_fabien(&self) -> Point2<f32> {
+        let fabien_hitbox = self.fabien.get_hitbox();
+        let mut pos = Point2::new(fabien_hitbox.x, fabien_hitbox.y);
+
+        for _ in 0..20 {
+            let angle = rand(2.0 * std::f32::consts::PI);
+            let dist = 60.0 + rand(60.0);
+            pos = Point2::new(
+                (fabien_hitbox.x + angle.cos() * dist).max(0.0).min(self.map_size.0 - 10.0),
+                (fabien_hitbox.y + angle.sin() * dist).max(0.0).min(self.map_size.1 - 10.0)
+            );
+            if self.map.is_free(Rect::new(pos.x, pos.y, 10.0, 10.0)) { break; }
+        }
+
+        pos
+    }
+
     fn draw_infos(&self, ctx: &mut Context) -> GameResult {
+        // Everything looks a bit icy during a time freeze
+        if self.fabien.is_time_frozen() {
+            let ice = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(),
+                self.ui.area(), graphics::Color::new(0.5, 0.75, 1.0, 0.15))?;
+            graphics::draw(ctx, &ice, graphics::DrawParam::default())?;
+        }
+
         let minutes = (self.time_passed / 60.0).floor();
         let seconds = (self.time_passed - minutes * 60.0).floor();
-        let infos = format!("{:02}:{:02}\nVague {}\nScore {}",
-                    minutes, seconds, self.wave, self.fabien.get_score());
+        let clock = match self.mode.time_limit() {
+            // Time Attack shows how much time is left instead
+            Some(limit) => {
+                let time_left = (limit - self.time_passed).max(0.0).ceil();
+                let minutes = (time_left / 60.0).floor();
+                format!("Reste {:02}:{:02}", minutes, time_left - minutes * 60.0)
+            },
+            None => format!("{:02}:{:02}", minutes, seconds)
+        };
+        // Say how many are left when they all have to die for the wave to end
+        let wave_def = &self.wave_def;
+        let wave = if wave_def.clear_to_advance && self.intermission <= 0.0 {
+            format!("Vague {} (reste {})", self.wave, self.bertrands_left()
+                + (wave_def.spawn_budget - self.wave_spawned.min(wave_def.spawn_budget)) as usize)
+        } else {
+            format!("Vague {}", self.wave)
+        };
+        let wave = if lighting::is_night(self.lighting.get_darkness()) { format!("{} - Nuit", wave) } else { wave };
+        let infos = format!("{}\n{}\nScore {}\nPièces {}\n{} - {}",
+                    clock, wave, self.fabien.get_score(), self.fabien.get_coins(),
+                    self.mode.name(), self.difficulty.name());
 
         let mut infos_text = Text::new(ctx, infos, "/Fonts/arial_narrow_7.ttf".to_string(),
                                     100.0, graphics::Color::from_rgb(255, 255, 255))?;
-        infos_text.set_pos(Point2::new(self.fabien.get_camera().x + 1.0,
-                self.fabien.get_camera().y + self.fabien.get_camera().h - infos_text.height(ctx) * 0.07));
+        const SCALE: f32 = 0.21;
+        infos_text.set_pos(self.ui.place(Anchor::BottomLeft,
+                (infos_text.width(ctx) * SCALE, infos_text.height(ctx) * SCALE), (3.0, 3.0)));
 
         graphics::queue_text(ctx, infos_text.get_ggez_text(), Point2::new(0.0, 0.0), None);
         graphics::draw_queued_text(ctx, graphics::DrawParam::new()
-            .scale(ggez::nalgebra::Vector2::new(0.07, 0.07))
+            .scale(ggez::nalgebra::Vector2::new(SCALE, SCALE))
             .dest(infos_text.get_pos()), None, graphics::FilterMode::Nearest)?;
 
+        let bertrands: Vec<Rect> = self.bertrands.iter().filter(|b| !b.is_dying()).map(|b| b.get_hitbox()).collect();
+        let powerups: Vec<Rect> = self.powerups.iter().map(|p| p.get_hitbox()).collect();
+        self.minimap.draw(ctx, &self.ui, self.camera.get_view(), self.fabien.get_hitbox(), &bertrands, &powerups)?;
+
         Ok(())
     }
 
-    fn shade_rect(&self, ctx: &mut Context) -> GameResult {
-        let (width, height) = if self.map_size.0 > self.screen_size.0 { 
-            self.map_size
-        } else {
-            self.screen_size
+    // The game as it's being played, the pause menu goes on top of it. The
+    // world is seen through the camera, the HUD is on the UI canvas.
+    fn draw_game(&mut self, ctx: &mut Context) -> GameResult {
+        graphics::set_screen_coordinates(ctx, self.camera.get_view())?;
+        self.map.draw(ctx)?;
+        self.decals.draw(ctx)?;
+        for p in self.particles.iter() { p.draw(ctx)?; }
+        self.draw_world(ctx, true)?;
+        self.draw_lighting(ctx)?;
+        if self.debug.is_shown() { self.debug.draw_hitboxes(ctx, &self.hitboxes())?; }
+
+        self.ui.begin(ctx)?;
+        self.fabien.draw_infos(ctx, &self.ui)?;
+        self.draw_infos(ctx)?;
+        self.draw_wave_banner(ctx)?;
+        if self.debug.is_shown() {
+            let stats = self.debug_stats(ctx);
+            self.debug.draw_stats(ctx, &self.ui, &stats)?;
+        }
+        self.ui.end(ctx)
+    }
+
+    // Everything that can hit or be hit, for the debug overlay
+    fn hitboxes(&self) -> Vec<(Rect, HitboxKind)> {
+        let mut hitboxes: Vec<(Rect, HitboxKind)> = self.map.get_obstacles().iter()
+            .filter(|obstacle| !obstacle.get_kind().is_flat())
+            .map(|obstacle| (obstacle.get_hitbox(), HitboxKind::Obstacle))
+            .collect();
+        hitboxes.extend(self.powerups.iter().map(|p| (p.get_hitbox(), HitboxKind::Powerup)));
+        for b in self.bertrands.iter() {
+            hitboxes.push((b.get_hitbox(), HitboxKind::Bertrand));
+            if let Some(attack) = b.get_attack_hitbox() { hitboxes.push((attack, HitboxKind::Attack)); }
+        }
+        hitboxes.extend(self.fabien.get_shot_hitboxes().map(|hitbox| (hitbox, HitboxKind::Bullet)));
+        hitboxes.push((self.fabien.get_hitbox(), HitboxKind::Fabien));
+
+        hitboxes
+    }
+
+    // What the debug overlay says, one line each
+    fn debug_stats(&self, ctx: &mut Context) -> Vec<String> {
+        let wave_def = &self.wave_def;
+        let dying = self.bertrands.iter().filter(|b| b.is_dying()).count();
+        let mut lines = vec![
+            format!("FPS {:.0} - image {:.1} ms", ggez::timer::fps(ctx),
+                ggez::timer::average_delta(ctx).as_secs_f64() * 1000.0),
+            format!("Bertrands {} (+{} morts) - bonus {}", self.bertrands.len() - dying, dying, self.powerups.len()),
+            format!("Balles {} - particules {}", self.fabien.get_shot_hitboxes().count(), self.particles.len()),
+            format!("Vague {} : {:.1} / {:.0} s - pause {:.1} s", self.wave, self.wave_time, wave_def.duration,
+                self.intermission.max(0.0)),
+            format!("Apparus {} / {} - tués {}", self.wave_spawned, wave_def.spawn_budget, self.wave_kills),
+            format!("Bertrand : {:.1} s (taux {:.2})", self.sec_since_last_bertrand, self.spawn_rate()),
+            format!("Bonus : {:.1} s (taux {:.2})", self.sec_since_last_powerup, self.powerup_rate()),
+            format!("Nuit {:.2}", self.lighting.get_darkness())
+        ];
+        if self.use_director { lines.push(format!("Pression {:.2}", self.director.get_pressure())); }
+        lines.push(format!("Graine {}", get_seed()));
+
+        lines
+    }
+
+    // The obstacles, and everyone if there's a game going on, from the back
+    // to the front so that whoever's lower on the screen is in front
+    fn draw_world(&mut self, ctx: &mut Context, entities: bool) -> GameResult {
+        let mut queue = RenderQueue::new(self.map.get_obstacles().len() + self.bertrands.len() + self.powerups.len() + 1);
+        for (i, obstacle) in self.map.get_obstacles().iter().enumerate() {
+            if obstacle.get_kind().is_flat() { continue; }
+            let hitbox = obstacle.get_hitbox();
+            queue.push(hitbox.y + hitbox.h, Drawable::Obstacle(i));
+        }
+        if entities {
+            for (i, p) in self.powerups.iter().enumerate() {
+                queue.push(render::depth(&self.map, p.get_hitbox()), Drawable::Powerup(i));
+            }
+            for (i, b) in self.bertrands.iter().enumerate() {
+                queue.push(render::depth(&self.map, b.get_hitbox()), Drawable::Bertrand(i));
+            }
+            queue.push(render::depth(&self.map, self.fabien.get_hitbox()), Drawable::Fabien);
+        }
+
+        for drawable in queue.sorted() {
+            match drawable {
+                Drawable::Obstacle(i) => self.map.draw_obstacle(ctx, i)?,
+                Drawable::Powerup(i) => self.powerups[i].draw(ctx)?,
+                Drawable::Bertrand(i) => self.bertrands[i].draw(ctx)?,
+                Drawable::Fabien => self.fabien.draw(ctx)?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_lighting(&self, ctx: &mut Context) -> GameResult {
+        let hitbox = self.fabien.get_hitbox();
+        let powerups: Vec<Rect> = self.powerups.iter().map(|p| p.get_hitbox()).collect();
+        self.lighting.draw(ctx, self.camera.get_view(),
+            Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h / 2.0), &powerups)
+    }
+
+    // The "wave cleared" banner, with what happened during the wave and a countdown
+    fn draw_wave_banner(&self, ctx: &mut Context) -> GameResult {
+        let summary = match &self.last_wave {
+            Some(summary) if self.intermission > 0.0 => summary,
+            _ => return Ok(())
         };
 
+        let lines = [
+            (format!("Vague {} terminée !", summary.wave), 0.48),
+            (format!("Bertrands tués : {}   Score : +{} (dont bonus {})", summary.kills, summary.score, summary.bonus), 0.21),
+            (format!("Vague {} dans {}", self.wave, self.intermission.ceil()), 0.3)
+        ];
+
+        let mut y = self.ui.area().h / 5.0;
+        for (line, scale) in lines.iter() {
+            let text = Text::new(ctx, line.clone(), "/Fonts/arial_narrow_7.ttf".to_string(),
+                                100.0, graphics::Color::from_rgb(255, 255, 255))?;
+            let pos = Point2::new(self.ui.place(Anchor::Top, (text.width(ctx) * scale, 0.0), (0.0, 0.0)).x, y);
+            y += text.height(ctx) * scale + 6.0;
+
+            graphics::queue_text(ctx, text.get_ggez_text(), Point2::new(0.0, 0.0), None);
+            graphics::draw_queued_text(ctx, graphics::DrawParam::new()
+                .scale(ggez::nalgebra::Vector2::new(*scale, *scale))
+                .dest(pos), None, graphics::FilterMode::Nearest)?;
+        }
+
+        Ok(())
+    }
+
+    // The edited map may be the one that's loaded, or a new one to pick in the settings
+    fn leave_editor(&mut self, ctx: &mut Context) -> GameResult {
+        self.editor = None;
+        self.menu.refresh_maps(ctx);
+        if self.map_path.as_deref() == Some(EDITOR_MAP_PATH) {
+            self.load_map(ctx, self.map_path.clone(), self.map_preset)?;
+        }
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, self.screen_size.0, self.screen_size.1))?;
+        self.game_state = GameState::Menu;
+
+        Ok(())
+    }
+
+    // Over whatever is on screen, which can go past the map when it's a small one
+    fn shade_rect(&self, ctx: &mut Context) -> GameResult {
+        let screen = graphics::screen_coordinates(ctx);
         let shade_rect = graphics::Mesh::new_rectangle(
             ctx,
             graphics::DrawMode::fill(),
-            Rect::new(0.0, 0.0, width, height),
+            screen,
             graphics::Color::new(0.0, 0.0, 0.0, 0.9)
         ).unwrap();
         graphics::draw(ctx, &shade_rect, graphics::DrawParam::default())?;
@@ -261,14 +781,106 @@ impl MainState {
         Ok(())
     }
 
+    fn start_game(&mut self, ctx: &mut Context, daily: Option<DailyChallenge>) -> GameResult {
+        let was_daily = self.daily.is_some();
+
+        // The daily challenge is the same map and the same rules for everyone
+        match &daily {
+            Some(challenge) => {
+                challenge.start(&mut self.storage);
+                self.daily_save_error = self.storage.save(ctx).err().map(|e| e.to_string());
+                set_seed(challenge.get_seed());
+                self.difficulty = Difficulty::Normal;
+                self.mode = GameMode::Survival;
+                self.use_director = false;
+            },
+            None => {
+                set_seed(random_seed());
+                self.difficulty = self.menu.get_difficulty();
+                self.mode = self.menu.get_mode();
+                self.use_director = self.menu.is_director_enabled();
+            }
+        }
+
+        // The map has to come from the seed too, and a new one is needed after a
+        // daily challenge. The daily challenge is always played on a medium random map.
+        let (map_path, map_preset) = match daily {
+            Some(_) => (None, MapSize::Medium),
+            None => (self.menu.get_map(), self.menu.get_map_size())
+        };
+        let preset_changed = map_path.is_none() && map_preset != self.map_preset;
+        if (daily.is_some() || was_daily || map_path != self.map_path || preset_changed)
+            && !self.load_map(ctx, map_path, map_preset)?
+        {
+            self.game_state = GameState::Menu;
+            return Ok(());
+        }
+        self.menu.set_notice(ctx, String::new(), (self.ui.area().w, self.ui.area().h));
+        self.fabien.set_map(self.map_size, self.map.get_spawn());
+        self.camera.set_map_size(self.map_size);
+        self.camera.snap_to(self.map.get_spawn());
+
+        self.minimap.set_size(self.menu.get_minimap_size());
+        self.lighting.set_quality(self.menu.get_lighting());
+        self.ui.set_scale(self.menu.get_ui_scale());
+
+        self.daily = daily;
+        self.fabien.apply_difficulty(self.difficulty, self.mode);
+        self.game_state = GameState::Playing;
+
+        Ok(())
+    }
+
+    // A hand-made map, or a random one when there's no path (or the map is broken).
+    // If something's wrong with the map the player is told in the menu, and it
+    // returns false so that the game doesn't start: clicking again plays anyway.
+    fn load_map(&mut self, ctx: &mut Context, map_path: Option<String>, map_preset: MapSize) -> GameResult<bool> {
+        let mut notice = String::new();
+        self.map = match &map_path {
+            Some(path) => match Map::from_tiled(ctx, path) {
+                Ok(map) => {
+                    if !map.get_warnings().is_empty() {
+                        notice = format!("La carte a des problèmes, Jouer pour y jouer quand même :\n{}",
+                            map.get_warnings().join("\n"));
+                    }
+                    map
+                },
+                Err(e) => {
+                    notice = format!("Impossible de charger la carte, Jouer pour jouer sur une carte aléatoire :\n{}", e);
+                    Map::new(ctx, map_preset)?
+                }
+            },
+            None => Map::new(ctx, map_preset)?
+        };
+        let loaded = notice.is_empty();
+        self.menu.set_notice(ctx, notice, (self.ui.area().w, self.ui.area().h));
+        self.map_path = map_path;
+        self.map_preset = map_preset;
+        self.minimap.rebuild(ctx, &self.map)?;
+        self.map_size = (self.map.get_width(), self.map.get_height());
+        self.cluster_center = (self.map_size.0 / 4.0, self.map_size.1 / 4.0);
+
+        Ok(loaded)
+    }
+
     fn reset(&mut self) {
         self.time_passed = 0.0;
         self.wave = 1;
-        self.stats = Stats { bertrand_killed: 0, shots: 0,
-            powerups_activated: 0, hits_taken: 0, time_played: 0 };
-        self.fabien.reset(self.screen_size);
+        self.wave_def = self.waves.get(1);
+        self.wave_time = 0.0;
+        self.wave_spawned = 0;
+        self.wave_kills = 0;
+        self.wave_start_score = 0;
+        self.intermission = 0.0;
+        self.last_wave = None;
+        self.stats = Stats::default();
+        self.particles.clear();
+        self.fabien.reset(self.map.get_spawn());
         self.bertrands.clear();
         self.powerups.clear();
+        self.decals.clear();
+        self.lighting.clear();
+        self.director.reset();
     }
 }
 
@@ -283,24 +895,67 @@ impl event::EventHandler for MainState {
                 let fps = ggez::timer::fps(ctx);
 
                 self.check_collisions(ctx)?;
-                self.fabien.update(ctx, self.map.get_trees())?;
+                if self.use_director {
+                    self.director.update(self.time_passed, 1.0 / fps as f32, self.fabien.get_health(),
+                        self.fabien.get_max_health(), self.fabien.get_nb_ammos());
+                }
+                self.fabien.update(ctx, &self.map)?;
+                let hitbox = self.fabien.get_hitbox();
+                self.camera.update(ctx, Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h / 2.0));
+                // The day goes by with the waves
+                let progress = if self.wave_def.duration > 0.0 { self.wave_time / self.wave_def.duration } else { 0.0 };
+                let darkness = lighting::darkness(self.wave, progress as f32);
+                self.lighting.update(ggez::timer::delta(ctx).as_secs_f32(), darkness);
+                // During a time freeze, only the ones already dying keep going
+                let frozen = self.fabien.is_time_frozen();
+                if let Some(noise) = self.fabien.take_noise() {
+                    self.lighting.flash(noise);
+                    if !frozen { for b in self.bertrands.iter_mut() { b.hear_noise(noise); } }
+                }
                 for b in self.bertrands.iter_mut() {
-                    b.update(ctx, self.fabien.get_hitbox(), self.map.get_trees())?;
+                    if frozen && !b.is_dying() { continue; }
+                    b.update(ctx, self.fabien.get_hitbox(), &self.map, darkness)?;
                 }
+                for p in self.particles.iter_mut() { p.update(ctx); }
+                self.particles.retain(|p| !p.is_dead());
+                self.bertrands.retain(|b| !b.is_dead());
+                self.decals.update(ctx);
                 for p in self.powerups.iter_mut() {
                     p.update(ctx, self.time_passed, fps)?;
                 }
+                self.powerups.retain(|p| !p.is_expired());
+                if let Some((radius, strength)) = self.fabien.get_magnet() {
+                    let hitbox = self.fabien.get_hitbox();
+                    let center = Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h / 2.0);
+                    for p in self.powerups.iter_mut() {
+                        p.pull_towards(center, radius, strength / fps as f32);
+                    }
+                }
 
                 self.time_passed += 1.0 / fps;
 
                 self.bertrand_spawning(ctx, fps)?;
                 self.powerup_spawning(ctx, fps)?;
 
-                // Check if Fabien is dead, if so it's Game Over
-                if self.fabien.get_health() <= 0 {
+                // Check if Fabien is dead (or out of time), if so it's Game Over
+                if self.mode.is_over(self.time_passed, self.fabien.get_health()) {
                     self.stats.time_played += self.time_passed as u64;
-                    self.game_over = Some(GameOver::new(ctx,
-                        self.fabien.get_score(), self.stats, self.screen_size)?);
+
+                    let daily_record = match &self.daily {
+                        Some(challenge) => {
+                            let record = challenge.record(&mut self.storage, self.fabien.get_score(), self.wave);
+                            if let Err(e) = self.storage.save(ctx) {
+                                self.daily_save_error = Some(e.to_string());
+                            }
+                            Some(record)
+                        },
+                        None => None
+                    };
+                    let save_error = self.daily_save_error.take();
+                    let daily = self.daily.as_ref().zip(daily_record)
+                        .map(|(challenge, record)| (challenge, record, save_error));
+                    self.game_over = Some(GameOver::new(ctx, self.fabien.get_score(), self.stats,
+                        self.difficulty, self.mode, daily, &self.ui)?);
 
                     graphics::set_screen_coordinates(ctx, 
                         Rect::new(0.0, 0.0, self.screen_size.0, self.screen_size.1))?;
@@ -310,58 +965,45 @@ impl event::EventHandler for MainState {
             GameState::GameOver => {
                 self.game_over.as_ref().unwrap().update();
             },
-            GameState::Pause => {}
+            GameState::Pause => {},
+            GameState::Editor => {
+                self.editor.as_mut().unwrap().update(ctx);
+            }
         }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        // println!("FPS: {}", ggez::timer::fps(ctx));
         graphics::clear(ctx, graphics::Color::from_rgb(104, 159, 56));
         match self.game_state {
             GameState::Menu => {
                 self.map.draw(ctx)?;
-                self.map.draw_trees_before(ctx)?;
-                self.map.draw_trees_after(ctx)?;
+                self.draw_world(ctx, false)?;
                 self.shade_rect(ctx)?;
+                self.ui.begin(ctx)?;
                 self.menu.draw(ctx)?;
+                self.ui.end(ctx)?;
             },
             GameState::Playing => {
-                self.map.draw(ctx)?;
-                for p in self.powerups.iter() {
-                    p.draw(ctx)?;
-                }
-                for b in self.bertrands.iter_mut() {
-                    b.draw(ctx)?;
-                }
-                self.map.draw_trees_before(ctx)?;
-                self.fabien.draw(ctx)?;
-                self.map.draw_trees_after(ctx)?;
-                self.fabien.draw_infos(ctx)?;
-                self.draw_infos(ctx)?;
+                self.draw_game(ctx)?;
             },
             GameState::GameOver => {
                 self.map.draw(ctx)?;
-                self.map.draw_trees_before(ctx)?;
-                self.map.draw_trees_after(ctx)?;
+                self.draw_world(ctx, false)?;
                 self.shade_rect(ctx)?;
+                self.ui.begin(ctx)?;
                 self.game_over.as_ref().unwrap().draw(ctx)?;
+                self.ui.end(ctx)?;
             },
             GameState::Pause => {
-                self.map.draw(ctx)?;
-                for p in self.powerups.iter() {
-                    p.draw(ctx)?;
-                }
-                for b in self.bertrands.iter_mut() {
-                    b.draw(ctx)?;
-                }
-                self.map.draw_trees_before(ctx)?;
-                self.fabien.draw(ctx)?;
-                self.map.draw_trees_after(ctx)?;
-                self.fabien.draw_infos(ctx)?;
-                self.draw_infos(ctx)?;
+                self.draw_game(ctx)?;
                 self.shade_rect(ctx)?;
-                self.pause.as_ref().unwrap().draw(ctx, self.fabien.get_camera())?;
+                self.ui.begin(ctx)?;
+                self.pause.as_ref().unwrap().draw(ctx, &self.ui)?;
+                self.ui.end(ctx)?;
+            },
+            GameState::Editor => {
+                self.editor.as_ref().unwrap().draw(ctx)?;
             }
         }
 
@@ -369,7 +1011,7 @@ impl event::EventHandler for MainState {
         Ok(())
     }
 
-    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods, _repeat: bool) {
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymod: KeyMods, _repeat: bool) {
         match self.game_state {
             GameState::Menu => {},
             GameState::Playing => {
@@ -380,11 +1022,19 @@ impl event::EventHandler for MainState {
                 }
                 self.fabien.key_down_event(keycode).unwrap();
 
+                // M shows or hides the minimap, + and - change its size
+                match keycode {
+                    event::KeyCode::M => self.minimap.toggle(),
+                    event::KeyCode::Add | event::KeyCode::Equals => self.minimap.grow(),
+                    event::KeyCode::Subtract | event::KeyCode::Minus => self.minimap.shrink(),
+                    _ => {}
+                }
+
                 if let event::KeyCode::Escape = keycode {
                     self.fabien.clear_movement();
                     graphics::set_screen_coordinates(ctx,
                         graphics::Rect::new(0.0, 0.0, self.screen_size.0, self.screen_size.1)).unwrap();
-                    self.pause = Some(Pause::new(ctx, self.fabien.get_camera()).unwrap());
+                    self.pause = Some(Pause::new(ctx, &self.ui).unwrap());
                     self.game_state = GameState::Pause;
                 }
             },
@@ -393,10 +1043,16 @@ impl event::EventHandler for MainState {
                 if let event::KeyCode::Escape = keycode {
                     self.game_state = GameState::Playing;
                 }
+            },
+            GameState::Editor => {
+                if self.editor.as_mut().unwrap().key_down_event(ctx, keycode, keymod) == 1 {
+                    self.leave_editor(ctx).unwrap();
+                }
             }
         }
 
         match keycode {
+            event::KeyCode::F3 => self.debug.toggle(),
             event::KeyCode::F11 => {
                 if self.fullscreen {
                     self.fullscreen = false;
@@ -424,37 +1080,58 @@ impl event::EventHandler for MainState {
                 self.fabien.key_up_event(keycode); 
             },
             GameState::GameOver => {},
-            GameState::Pause => {}
+            GameState::Pause => {},
+            GameState::Editor => {}
         }
     }
 
     fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
         match self.game_state {
             GameState::Menu => {
-                if self.menu.mouse_button_down_event(ctx, x, y, button, self.screen_size) == 1 {
-                    self.game_state = GameState::Playing;
+                let pos = self.ui.screen_to_ui(x, y);
+                let choice = self.menu.mouse_button_down_event(ctx, pos.x, pos.y, button, self.screen_size);
+                // The menu is on the UI canvas too, so a new scale shows right away
+                if self.menu.get_ui_scale() != self.ui.get_scale() {
+                    self.ui.set_scale(self.menu.get_ui_scale());
+                    self.menu.resize_event(ctx, self.ui.area().w, self.ui.area().h);
+                }
+                match choice {
+                    1 => self.start_game(ctx, None).unwrap(),
+                    2 => {
+                        let challenge = DailyChallenge::today(&self.storage);
+                        self.start_game(ctx, Some(challenge)).unwrap();
+                    },
+                    3 => {
+                        self.editor = Some(Editor::new(ctx, self.screen_size).unwrap());
+                        self.game_state = GameState::Editor;
+                    },
+                    _ => {}
                 }
             },
             GameState::Playing => {
-                self.fabien.mouse_button_down_event(ctx, button, x, y, self.screen_size);
+                let pos = self.camera.screen_to_world(x, y);
+                self.fabien.mouse_button_down_event(ctx, button, pos);
             },
             GameState::GameOver => { 
-                match self.game_over.as_ref().unwrap().mouse_button_down_event(button, x, y) {
+                let pos = self.ui.screen_to_ui(x, y);
+                match self.game_over.as_ref().unwrap().mouse_button_down_event(button, pos.x, pos.y) {
                     1 => {
                         self.reset();
+                        self.menu.set_daily_played(ctx, !DailyChallenge::today(&self.storage).is_scored());
                         self.game_state = GameState::Menu;
                     }
                     2 => {
                         self.reset();
-                        self.game_state = GameState::Playing;
+                        // Replaying the daily challenge is another (unscored) go at the same one
+                        let challenge = self.daily.as_ref().map(|_| DailyChallenge::today(&self.storage));
+                        self.start_game(ctx, challenge).unwrap();
                     },
                     _ => {}
                 }
             },
             GameState::Pause => {
-                match self.pause.as_ref().unwrap().mouse_button_down_event(button, x, y,
-                    self.fabien.get_camera(), self.screen_size)
-                {
+                let pos = self.ui.screen_to_ui(x, y);
+                match self.pause.as_ref().unwrap().mouse_button_down_event(button, pos) {
                     1 => self.game_state = GameState::Playing,
                     2 => {
                         self.fabien.set_health(0);
@@ -463,41 +1140,69 @@ impl event::EventHandler for MainState {
 
                     _ => {}
                 }
+            },
+            GameState::Editor => {
+                self.editor.as_mut().unwrap().mouse_button_down_event(button, x, y);
             }
         }
     }
 
-    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
+        if let GameState::Editor = self.game_state {
+            self.editor.as_mut().unwrap().mouse_button_up_event(button);
+        }
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
+        match self.game_state {
+            GameState::Playing => self.camera.zoom_by(y),
+            GameState::Editor => self.editor.as_mut().unwrap().mouse_wheel_event(y),
+            _ => {}
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, dx: f32, dy: f32) {
         match self.game_state {
             GameState::Menu => {
-                self.menu.mouse_motion_event(ctx, x, y);
+                let pos = self.ui.screen_to_ui(x, y);
+                self.menu.mouse_motion_event(ctx, pos.x, pos.y);
             },
             GameState::Playing => {},
             GameState::GameOver => {
-                self.game_over.as_mut().unwrap().mouse_motion_event(ctx, x, y);
+                let pos = self.ui.screen_to_ui(x, y);
+                self.game_over.as_mut().unwrap().mouse_motion_event(ctx, pos.x, pos.y);
             },
             GameState::Pause => {
-                self.pause.as_mut().unwrap().mouse_motion_event(ctx, x, y, self.fabien.get_camera(), self.screen_size);
+                let pos = self.ui.screen_to_ui(x, y);
+                self.pause.as_mut().unwrap().mouse_motion_event(ctx, pos);
+            },
+            GameState::Editor => {
+                self.editor.as_mut().unwrap().mouse_motion_event(x, y, dx, dy);
             }
         }
     }
 
     fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32)  {
         self.screen_size = (width, height);
-        self.fabien.resize_event(width, height);
+        self.camera.resize((width, height));
+        self.ui.resize((width, height));
         match self.game_state {
             GameState::Menu => {
-                self.menu.resize_event(ctx, width, height);
+                self.menu.resize_event(ctx, self.ui.area().w, self.ui.area().h);
                 ggez::graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width, height)).unwrap();
             },
             GameState::Playing => {},
             GameState::GameOver => {
                 ggez::graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width, height)).unwrap();
-                self.game_over.as_mut().unwrap().resize_event(ctx, width, height);
-                self.menu.resize_event(ctx, width, height);
+                self.game_over.as_mut().unwrap().resize_event(ctx, &self.ui);
+                self.menu.resize_event(ctx, self.ui.area().w, self.ui.area().h);
             },
             GameState::Pause => {
-                self.pause.as_mut().unwrap().resize_event(ctx, self.fabien.get_camera());
+                self.pause.as_mut().unwrap().resize_event(ctx, &self.ui);
+            },
+            GameState::Editor => {
+                self.editor.as_mut().unwrap().resize_event(width, height);
+                self.menu.resize_event(ctx, self.ui.area().w, self.ui.area().h);
             }
         }
 
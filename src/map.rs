@@ -1,25 +1,349 @@
 use ggez::{
-    graphics, Context, GameResult,
+    graphics, Context, GameResult, GameError,
     graphics::{ spritebatch, Rect },
     nalgebra::Point2
 };
 
-use crate::utils::rand;
+use std::collections::{ HashMap, VecDeque };
+use rand::Rng;
+use crate::utils::{ rand, game_rng };
+use crate::forest::{ Forest, PATH_WIDTH };
+use crate::tiled::{ TiledMap, SpawnKind };
+use crate::terrain::ObstacleKind;
+use crate::map_size::MapSize;
+
+// Size of the cells of the grid used to know where Fabien can go
+const CELL_SIZE: f32 = 8.0;
+// Nothing gets placed closer than this to the edges of the map
+const EDGE_MARGIN: f32 = 16.0;
+// Closed off areas smaller than this (in cells) are left closed
+const MIN_POCKET_SIZE: usize = 24;
+// Past that many cells looked at, a path is given up on
+const MAX_PATH_CELLS: usize = 6000;
+// Size of the buckets the obstacles are sorted in, bigger than any of them
+const BUCKET_SIZE: f32 = 64.0;
+// Bits of grass per million square units of map
+const GRASS_DENSITY: f32 = 750.0;
 
 struct Sprite {
     image: spritebatch::SpriteBatch,
 }
 
-pub struct Tree {
+// Sprites of the obstacles of the generated maps, and what they are
+const OBSTACLE_SPRITES: [(ObstacleKind, &str); 6] = [
+    (ObstacleKind::Tree, "/tree_0.png"),
+    (ObstacleKind::Tree, "/tree_1.png"),
+    (ObstacleKind::Rock, "/rock.png"),
+    (ObstacleKind::Bush, "/bush.png"),
+    (ObstacleKind::Water, "/water.png"),
+    (ObstacleKind::Mud, "/mud.png")
+];
+
+pub struct Obstacle {
+    kind: ObstacleKind,
     sprite: graphics::Image,
     hitbox: Rect,
     position: Point2<f32>,
-    draw_before_fabien: bool
+    // Bullets it can still take, None if it can't be broken
+    durability: Option<u8>
 }
 
-impl Tree { 
+impl Obstacle {
+    // Only the bottom of the sprite (the trunk of a tree) is solid, see ObstacleKind::solid_part
+    pub fn new(kind: ObstacleKind, sprite: graphics::Image, position: Point2<f32>) -> Obstacle {
+        let mut obstacle = Obstacle {
+            kind: kind,
+            sprite: sprite,
+            hitbox: Rect::new(0.0, 0.0, 0.0, 0.0),
+            position: position,
+            durability: kind.durability()
+        };
+        obstacle.set_position(position);
+        obstacle
+    }
+
+    pub fn set_position(&mut self, position: Point2<f32>) {
+        let (width, height) = (self.sprite.width() as f32, self.sprite.height() as f32);
+        let solid = height * self.kind.solid_part();
+        self.position = position;
+        self.hitbox = Rect::new(position.x, position.y + height - solid, width, solid);
+    }
+
+    pub fn get_kind(&self) -> ObstacleKind { self.kind }
+    pub fn get_position(&self) -> Point2<f32> { self.position }
+
+    pub fn draw(&self, ctx: &mut Context, color: graphics::Color) -> GameResult {
+        let param = graphics::DrawParam::new()
+            .dest(self.position)
+            .color(color);
+        graphics::draw(ctx, &self.sprite, param)
+    }
+
+    // It gets darker the more it's been shot at
+    fn tint(&self) -> graphics::Color {
+        match (self.durability, self.kind.durability()) {
+            (Some(left), Some(full)) => {
+                let shade = 1.0 - 0.4 * (1.0 - left as f32 / full as f32);
+                graphics::Color::new(shade, shade, shade, 1.0)
+            },
+            _ => graphics::WHITE
+        }
+    }
+
+    pub fn take_hit(&mut self) {
+        if let Some(left) = self.durability.as_mut() { *left = left.saturating_sub(1); }
+    }
+
+    pub fn is_destroyed(&self) -> bool { self.durability == Some(0) }
+
     pub fn get_hitbox(&self) -> Rect { self.hitbox } 
-    pub fn draw_before_fabien(&mut self, draw: bool) { self.draw_before_fabien = draw; }
+    // The whole sprite, leaves included for a tree, which is what blocks the view
+    pub fn get_sight_box(&self) -> Rect {
+        Rect::new(self.position.x, self.position.y, self.sprite.width() as f32, self.sprite.height() as f32)
+    }
+}
+
+// Which parts of the map can't be walked through, to check if a spot can be reached
+struct WalkGrid {
+    cols: usize,
+    rows: usize,
+    blocked: Vec<bool>,
+    // Which open area each cell is in, cells with the same one can be reached
+    // from each other. None for the blocked ones.
+    areas: Vec<Option<usize>>
+}
+
+impl WalkGrid {
+    fn new(width: f32, height: f32, obstacles: &[Obstacle]) -> WalkGrid {
+        let cols = (width / CELL_SIZE).ceil() as usize;
+        let rows = (height / CELL_SIZE).ceil() as usize;
+        let mut grid = WalkGrid { cols: cols, rows: rows, blocked: vec![false; cols * rows], areas: vec![] };
+
+        for obstacle in obstacles.iter().filter(|obstacle| obstacle.kind.blocks_walking()) {
+            for cell in grid.cells_of(obstacle.get_hitbox(), 0) { grid.blocked[cell] = true; }
+        }
+        grid.areas = grid.find_areas();
+
+        grid
+    }
+
+    // Floods every open area once, so that checking if a spot can be reached doesn't have to
+    fn find_areas(&self) -> Vec<Option<usize>> {
+        let mut areas = vec![None; self.blocked.len()];
+        let mut visited = vec![false; self.blocked.len()];
+        let mut nb_areas = 0;
+        for cell in 0..self.blocked.len() {
+            if visited[cell] || self.blocked[cell] { continue; }
+            for open in self.flood(cell, &mut visited) { areas[open] = Some(nb_areas); }
+            nb_areas += 1;
+        }
+
+        areas
+    }
+
+    // The cells right next to it, not the diagonal ones
+    fn neighbours(&self, cell: usize) -> [Option<usize>; 4] {
+        let (col, row) = (cell % self.cols, cell / self.cols);
+        [
+            (col > 0).then(|| cell - 1),
+            (col + 1 < self.cols).then(|| cell + 1),
+            (row > 0).then(|| cell - self.cols),
+            (row + 1 < self.rows).then(|| cell + self.cols)
+        ]
+    }
+
+    fn cell_of(&self, pos: Point2<f32>) -> usize {
+        let col = ((pos.x / CELL_SIZE).max(0.0) as usize).min(self.cols - 1);
+        let row = ((pos.y / CELL_SIZE).max(0.0) as usize).min(self.rows - 1);
+        row * self.cols + col
+    }
+
+    // All the cells under the rectangle, plus a border of that many cells around it
+    fn cells_of(&self, rect: Rect, border: usize) -> Vec<usize> {
+        let first = self.cell_of(Point2::new(rect.x, rect.y));
+        let last = self.cell_of(Point2::new(rect.x + rect.w, rect.y + rect.h));
+        let (first_col, first_row) = ((first % self.cols).saturating_sub(border), (first / self.cols).saturating_sub(border));
+        let last_col = (last % self.cols + border).min(self.cols - 1);
+        let last_row = (last / self.cols + border).min(self.rows - 1);
+
+        (first_row..=last_row)
+            .flat_map(|row| (first_col..=last_col).map(move |col| row * self.cols + col))
+            .collect()
+    }
+
+    // Breadth first search to find every cell that can be reached from the
+    // first one, which is considered open even if it isn't (Fabien can be
+    // rubbing against a tree). Those get marked in visited, and returned.
+    fn flood(&self, start: usize, visited: &mut [bool]) -> Vec<usize> {
+        let mut cells = vec![];
+        let mut queue = VecDeque::new();
+        visited[start] = true;
+        queue.push_back(start);
+
+        while let Some(cell) = queue.pop_front() {
+            cells.push(cell);
+
+            for next in self.neighbours(cell).iter().flatten() {
+                if !visited[*next] && !self.blocked[*next] {
+                    visited[*next] = true;
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        cells
+    }
+
+    // Like in flood, the first cell is considered open: if it's blocked, it's
+    // the areas around it that count
+    fn is_reachable(&self, from: usize, to: usize) -> bool {
+        let area = match self.areas[to] {
+            Some(area) => area,
+            None => return false
+        };
+        if self.areas[from].is_some() { return self.areas[from] == Some(area); }
+
+        self.neighbours(from).iter().flatten().any(|cell| self.areas[*cell] == Some(area))
+    }
+
+    // Breadth first search for the way from a cell to another, for something
+    // that many cells wide and tall (its top left corner is in the cell). Gives
+    // the cells to go through, the first one left out, or None if it's closed
+    // off or too far away. The first cell is considered open, like in flood.
+    fn path(&self, from: usize, to: usize, size: (usize, usize)) -> Option<Vec<usize>> {
+        let fits = |cell: usize| {
+            let (col, row) = (cell % self.cols, cell / self.cols);
+            col + size.0 <= self.cols && row + size.1 <= self.rows
+                && (row..row + size.1).all(|r| (col..col + size.0).all(|c| !self.blocked[r * self.cols + c]))
+        };
+        if !fits(to) { return None; }
+
+        let mut came_from = HashMap::new();
+        let mut queue = VecDeque::new();
+        came_from.insert(from, from);
+        queue.push_back(from);
+
+        while let Some(cell) = queue.pop_front() {
+            if cell == to {
+                let mut path = vec![];
+                let mut cell = to;
+                while cell != from {
+                    path.push(cell);
+                    cell = came_from[&cell];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if came_from.len() > MAX_PATH_CELLS { return None; }
+
+            for next in self.neighbours(cell).iter().flatten() {
+                if !came_from.contains_key(next) && fits(*next) {
+                    came_from.insert(*next, cell);
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        None
+    }
+
+    // The open areas that can't be reached from the start, if they're big
+    // enough to matter (a tiny gap between three trees doesn't)
+    fn pockets(&self, start: usize) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.blocked.len()];
+        self.flood(start, &mut visited);
+
+        let mut pockets = vec![];
+        for cell in 0..self.blocked.len() {
+            if visited[cell] || self.blocked[cell] { continue; }
+            let pocket = self.flood(cell, &mut visited);
+            if pocket.len() >= MIN_POCKET_SIZE { pockets.push(pocket); }
+        }
+
+        pockets
+    }
+}
+
+// The obstacles sorted by where they are on the map, so that only the ones
+// around a spot are checked
+struct Buckets {
+    cols: usize,
+    rows: usize,
+    // Indices in the list of obstacles, one that's on several buckets is in each of them
+    obstacles: Vec<Vec<usize>>
+}
+
+impl Buckets {
+    fn new(width: f32, height: f32, obstacles: &[Obstacle]) -> Buckets {
+        let cols = (width / BUCKET_SIZE).ceil().max(1.0) as usize;
+        let rows = (height / BUCKET_SIZE).ceil().max(1.0) as usize;
+        let mut buckets = Buckets { cols: cols, rows: rows, obstacles: vec![vec![]; cols * rows] };
+
+        for (i, obstacle) in obstacles.iter().enumerate() {
+            for bucket in buckets.under(obstacle.get_hitbox()) { buckets.obstacles[bucket].push(i); }
+        }
+
+        buckets
+    }
+
+    fn under(&self, rect: Rect) -> Vec<usize> {
+        let col = |x: f32| ((x / BUCKET_SIZE).max(0.0) as usize).min(self.cols - 1);
+        let row = |y: f32| ((y / BUCKET_SIZE).max(0.0) as usize).min(self.rows - 1);
+        let (first_col, last_col) = (col(rect.x), col(rect.x + rect.w));
+        let (first_row, last_row) = (row(rect.y), row(rect.y + rect.h));
+
+        (first_row..=last_row)
+            .flat_map(|row| (first_col..=last_col).map(move |col| row * self.cols + col))
+            .collect()
+    }
+
+    // The obstacles that might be touching the rectangle, maybe more than once
+    fn around(&self, rect: Rect) -> impl Iterator<Item = usize> + '_ {
+        self.under(rect).into_iter().flat_map(move |bucket| self.obstacles[bucket].iter().copied())
+    }
+}
+
+// What's on a hand-made map, apart from the images
+struct TiledObjects {
+    // Kind, gid and top left corner
+    obstacles: Vec<(ObstacleKind, u32, Point2<f32>)>,
+    spawn: Point2<f32>,
+    bertrand_spawns: Vec<Point2<f32>>,
+    powerup_spawns: Vec<Point2<f32>>,
+    warnings: Vec<String>
+}
+
+impl TiledObjects {
+    // The path is only for the warnings
+    fn read(tiled_map: &TiledMap, path: &str) -> TiledObjects {
+        let (width, height) = tiled_map.pixel_size();
+        let mut objects = TiledObjects {
+            obstacles: vec![],
+            spawn: Point2::new(width / 2.0, height / 2.0),
+            bertrand_spawns: vec![],
+            powerup_spawns: vec![],
+            warnings: vec![]
+        };
+
+        for object in tiled_map.objects() {
+            if let Some(gid) = object.get_gid() {
+                // Trees when Tiled doesn't say what it is
+                let kind = ObstacleKind::from_name(&object.kind).unwrap_or(ObstacleKind::Tree);
+                objects.obstacles.push((kind, gid, object.top_left()));
+            } else if let Some(kind) = object.get_spawn() {
+                let pos = object.top_left();
+                match kind {
+                    SpawnKind::Fabien => objects.spawn = pos,
+                    SpawnKind::Bertrand => objects.bertrand_spawns.push(pos),
+                    SpawnKind::Powerup => objects.powerup_spawns.push(pos)
+                }
+            } else if object.kind == "spawn" {
+                objects.warnings.push(format!("{} : point d'apparition inconnu « {} », ignoré", path, object.name));
+            }
+        }
+
+        objects
+    }
 }
 
 pub struct Map {
@@ -27,77 +351,124 @@ pub struct Map {
     height: f32,
     background: graphics::Mesh,
     grass: Vec<Sprite>,
-    trees: Vec<Tree>,
+    obstacles: Vec<Obstacle>,
+    walk_grid: WalkGrid,
+    buckets: Buckets,
+    // Where Fabien starts, there's always room around it
+    spawn: Point2<f32>,
+    // Only hand-made maps have those, the other ones use the whole map
+    bertrand_spawns: Vec<Point2<f32>>,
+    powerup_spawns: Vec<Point2<f32>>,
+    // What was wrong with a hand-made map but didn't stop it from loading
+    warnings: Vec<String>
 }
 
 impl Map {
-    pub fn new(ctx: &mut Context, width: f32, height: f32) -> GameResult<Map> {
-        const NUMBERS_OF: [u16; 2] = [3000, 100];
-        let environment = ["grass", "tree"];
-        let ranges: [u16; 2] = [4, 1];
-
-        let background = graphics::Mesh::new_polygon(
-            ctx,
-            graphics::DrawMode::fill(),
-            &[Point2::new(0.0, 0.0), Point2::new(0.0, height as f32),
-            Point2::new(width as f32, height as f32), Point2::new(width as f32, 0.0)],
-            graphics::Color::from_rgb(104, 159, 56)
-        )?;
+    pub fn new(ctx: &mut Context, size: MapSize) -> GameResult<Map> {
+        let (width, height) = size.dimensions();
+        let mut images = vec![];
+        for (_, path) in OBSTACLE_SPRITES.iter() { images.push(graphics::Image::new(ctx, *path)?); }
+        let sprites: Vec<(ObstacleKind, (f32, f32))> = OBSTACLE_SPRITES.iter().zip(images.iter())
+            .map(|((kind, _), image)| (*kind, (image.width() as f32, image.height() as f32)))
+            .collect();
+        let forest = Forest::generate(width, height, size.tree_density(), &sprites);
+
+        // The paths are drawn right on the ground, a bit more worn out than the rest
+        let path_color = graphics::Color::from_rgb(139, 160, 74);
+        let mut background = ground(width, height)?;
+        for path in forest.paths.iter() {
+            background.polyline(graphics::DrawMode::stroke(PATH_WIDTH * 0.6), path, path_color)?;
+            // Round the turns
+            for point in path.iter() {
+                background.circle(graphics::DrawMode::fill(), *point, PATH_WIDTH * 0.3, 0.5, path_color);
+            }
+        }
+        background.circle(graphics::DrawMode::fill(), forest.spawn, PATH_WIDTH, 0.5, path_color);
+        let background = background.build(ctx)?;
+
+        let mut obstacles: Vec<Obstacle> = forest.obstacles.iter()
+            .map(|(sprite, position)| Obstacle::new(OBSTACLE_SPRITES[*sprite].0, images[*sprite].clone(), *position))
+            .collect();
 
+        // Less grass where people walk all the time
         let mut grass = vec![];
-        let mut trees = vec![];
-
-        // All of this is really ugly code because I had to change a few things
-        // multiples times to accomodate for other aspects of the game.
-        // But does it work? Uh, I hope it does.
-        for (i, env) in environment.iter().enumerate() {
-            for id in 0..=ranges[i] {
-                let image = graphics::Image::new(ctx, format!("/{}_{}.png", env, id))?;
-                let (sprite_width, sprite_height) = (image.width(), image.height());
-                let mut spritebatch = spritebatch::SpriteBatch::new(image);
-
-                for _ in 0..NUMBERS_OF[i] {
-                    let x = rand(width);
-                    let y = rand(height);
-
-                    if *env == "tree" {
-                        let hitbox = Rect::new(
-                            x,
-                            y + (3 * sprite_height) as f32 / 5.0,
-                            sprite_width as f32,
-                            (2 * sprite_height) as f32 / 5.0
-                        );
-
-                        trees.push(Tree {
-                            sprite: graphics::Image::new(ctx, format!("/{}_{}.png", env, id))?,
-                            hitbox: hitbox,
-                            position: Point2::new(x, y),
-                            draw_before_fabien: true,
-                        });
-                    } else {
-                        let param = graphics::DrawParam::new()
-                            .dest(Point2::new(x, y));
-                        spritebatch.add(param);
-                    }
-                }
-                let mut sprite = Sprite {
-                    image: spritebatch
-                };
-                let param = graphics::DrawParam::new();
-                sprite.image.add(param);
-                match *env {
-                    "grass" => grass.push(sprite),
-                    _ => {}
-                }
+        let nb_grass = (GRASS_DENSITY * size.grass_density() * width * height / 1_000_000.0) as u32;
+        for id in 0..=4 {
+            let mut spritebatch = spritebatch::SpriteBatch::new(graphics::Image::new(ctx, format!("/grass_{}.png", id))?);
+            for _ in 0..nb_grass {
+                let pos = Point2::new(rand(width), rand(height));
+                if forest.is_on_path(pos, -PATH_WIDTH / 4.0) { continue; }
+                spritebatch.add(graphics::DrawParam::new().dest(pos));
             }
+            grass.push(Sprite { image: spritebatch });
         }
 
+        let walk_grid = open_pockets(width, height, &mut obstacles, forest.spawn);
+        let buckets = Buckets::new(width, height, &obstacles);
+
         let map = Map {
             width: width,
             height: height,
             background: background,
             grass: grass,
-            trees: trees,
+            obstacles: obstacles,
+            walk_grid: walk_grid,
+            buckets: buckets,
+            spawn: forest.spawn,
+            bertrand_spawns: vec![],
+            powerup_spawns: vec![],
+            warnings: vec![]
+        };
+
+        Ok(map)
+    }
+
+    // A hand-made map, see tiled.rs for what's expected in the file
+    pub fn from_tiled(ctx: &mut Context, path: &str) -> GameResult<Map> {
+        let tiled_map = TiledMap::load(ctx, path)?;
+        let (width, height) = tiled_map.pixel_size();
+
+        // Images are loaded once for all the tiles and objects using them
+        let mut images: HashMap<String, graphics::Image> = HashMap::new();
+        let mut image_of = |ctx: &mut Context, gid: u32| -> GameResult<graphics::Image> {
+            let path = tiled_map.tile_image(gid).ok_or_else(|| GameError::ResourceLoadError(
+                format!("{} : pas d'image pour la tuile {}", path, gid)))?;
+            if !images.contains_key(&path) {
+                images.insert(path.clone(), graphics::Image::new(ctx, &path)?);
+            }
+            Ok(images[&path].clone())
+        };
+
+        let mut batches: HashMap<String, spritebatch::SpriteBatch> = HashMap::new();
+        for (gid, pos) in tiled_map.tiles() {
+            let image = image_of(ctx, gid)?;
+            let path = tiled_map.tile_image(gid).unwrap_or_default();
+            batches.entry(path).or_insert_with(|| spritebatch::SpriteBatch::new(image))
+                .add(graphics::DrawParam::new().dest(pos));
+        }
+        let grass = batches.into_values().map(|batch| Sprite { image: batch }).collect();
+
+        let objects = TiledObjects::read(&tiled_map, path);
+        let mut obstacles = vec![];
+        for (kind, gid, pos) in objects.obstacles {
+            obstacles.push(Obstacle::new(kind, image_of(ctx, gid)?, pos));
+        }
+
+        let walk_grid = WalkGrid::new(width, height, &obstacles);
+        let buckets = Buckets::new(width, height, &obstacles);
+
+        let map = Map {
+            width: width,
+            height: height,
+            background: ground(width, height)?.build(ctx)?,
+            grass: grass,
+            obstacles: obstacles,
+            walk_grid: walk_grid,
+            buckets: buckets,
+            spawn: objects.spawn,
+            bertrand_spawns: objects.bertrand_spawns,
+            powerup_spawns: objects.powerup_spawns,
+            warnings: objects.warnings
         };
 
         Ok(map)
@@ -110,43 +481,269 @@ impl Map {
                 .dest(Point2::new(0.0, 0.0));
             graphics::draw(ctx, &grass.image, param)?; 
         }
+        // Water and mud are on the ground, under everything else
+        for obstacle in self.obstacles.iter().filter(|obstacle| obstacle.kind.is_flat()) {
+            obstacle.draw(ctx, graphics::WHITE)?;
+        }
 
         Ok(())
     }
 
-    pub fn draw_trees_before(&self, ctx: &mut Context) -> ggez::GameResult {
-        for tree in self.trees.iter() {
-            if tree.draw_before_fabien {
-                let param = graphics::DrawParam::new()
-                    .dest(Point2::new(tree.position.x, tree.position.y));
-                graphics::draw(ctx, &tree.sprite, param)?;
-            }
-        }
+    // The ones standing up, they go through the render queue with everything else
+    pub fn draw_obstacle(&self, ctx: &mut Context, index: usize) -> ggez::GameResult {
+        let obstacle = &self.obstacles[index];
+        obstacle.draw(ctx, obstacle.tint())
+    }
 
-        Ok(())
+    pub fn get_width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn get_obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    pub fn get_spawn(&self) -> Point2<f32> {
+        self.spawn
+    }
+
+    pub fn get_bertrand_spawns(&self) -> &[Point2<f32>] {
+        &self.bertrand_spawns
+    }
+
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    // Not in anything, and not stuck against the edges of the map
+    pub fn is_free(&self, rect: Rect) -> bool {
+        rect.x >= EDGE_MARGIN && rect.y >= EDGE_MARGIN
+            && rect.x + rect.w <= self.width - EDGE_MARGIN
+            && rect.y + rect.h <= self.height - EDGE_MARGIN
+            && !self.buckets.around(rect).any(|i| self.obstacles[i].get_hitbox().overlaps(&rect))
+    }
+
+    // What movement code asks before going somewhere
+    pub fn blocks_walking(&self, rect: Rect) -> bool {
+        self.buckets.around(rect).map(|i| &self.obstacles[i])
+            .any(|obstacle| obstacle.kind.blocks_walking() && obstacle.get_hitbox().overlaps(&rect))
     }
 
-    pub fn draw_trees_after(&self, ctx: &mut Context) -> ggez::GameResult {
-        for tree in self.trees.iter() {
-            if !tree.draw_before_fabien {
-                let param = graphics::DrawParam::new()
-                    .dest(Point2::new(tree.position.x, tree.position.y));
-                graphics::draw(ctx, &tree.sprite, param)?;
+    // By how much to multiply the speed of something there, the slowest ground wins
+    pub fn speed_factor(&self, rect: Rect) -> f32 {
+        self.buckets.around(rect).map(|i| &self.obstacles[i])
+            .filter(|obstacle| obstacle.get_hitbox().overlaps(&rect))
+            .map(|obstacle| obstacle.kind.speed_factor())
+            .fold(1.0, f32::min)
+    }
+
+    // In a bush, only the middle counts so that Fabien has to really be in it
+    pub fn hides(&self, rect: Rect) -> bool {
+        let middle = Point2::new(rect.x + rect.w / 2.0, rect.y + rect.h / 2.0);
+        self.buckets.around(Rect::new(middle.x, middle.y, 0.0, 0.0)).map(|i| &self.obstacles[i])
+            .any(|obstacle| obstacle.kind.hides() && obstacle.get_hitbox().contains(middle))
+    }
+
+    // The first obstacle that would stop a bullet there
+    pub fn obstacle_hit_by(&self, rect: Rect) -> Option<usize> {
+        self.buckets.around(rect)
+            .filter(|i| self.obstacles[*i].kind.stops_bullets() && self.obstacles[*i].get_hitbox().overlaps(&rect))
+            .min()
+    }
+
+    // The obstacles that can be broken with their trunk within the radius
+    pub fn obstacles_around(&self, center: Point2<f32>, radius: f32) -> Vec<usize> {
+        // Their middle is in the square around the circle, so they're in its buckets
+        let square = Rect::new(center.x - radius, center.y - radius, radius * 2.0, radius * 2.0);
+        let mut around: Vec<usize> = self.buckets.around(square)
+            .filter(|i| self.obstacles[*i].durability.is_some())
+            .filter(|i| {
+                let hitbox = self.obstacles[*i].get_hitbox();
+                let middle = Point2::new(hitbox.x + hitbox.w / 2.0, hitbox.y + hitbox.h / 2.0);
+                (middle - center).norm() <= radius
+            })
+            .collect();
+        around.sort_unstable();
+        around.dedup();
+
+        around
+    }
+
+    pub fn damage_obstacle(&mut self, index: usize) {
+        if let Some(obstacle) = self.obstacles.get_mut(index) { obstacle.take_hit(); }
+    }
+
+    // Takes the broken obstacles off the map (done after all the hits of a
+    // frame so that the indices stay the same until then), and opens the way
+    // where they were
+    pub fn remove_destroyed(&mut self) -> Vec<Obstacle> {
+        if !self.obstacles.iter().any(|obstacle| obstacle.is_destroyed()) { return vec![]; }
+
+        let (destroyed, obstacles) = self.obstacles.drain(..).partition(|obstacle| obstacle.is_destroyed());
+        self.obstacles = obstacles;
+        self.walk_grid = WalkGrid::new(self.width, self.height, &self.obstacles);
+        self.buckets = Buckets::new(self.width, self.height, &self.obstacles);
+
+        destroyed
+    }
+
+    pub fn is_reachable(&self, from: Point2<f32>, to: Point2<f32>) -> bool {
+        self.walk_grid.is_reachable(self.walk_grid.cell_of(from), self.walk_grid.cell_of(to))
+    }
+
+    // Where to go next to get something that big from one spot to another
+    // (both its top left corner) around what's in the way, one point per cell
+    // of the walk grid, None if there's no way or it's too far
+    pub fn find_path(&self, from: Point2<f32>, to: Point2<f32>, size: (f32, f32)) -> Option<Vec<Point2<f32>>> {
+        let grid = &self.walk_grid;
+        let size = ((size.0 / CELL_SIZE).ceil() as usize, (size.1 / CELL_SIZE).ceil() as usize);
+        let path = grid.path(grid.cell_of(from), grid.cell_of(to), size)?;
+
+        Some(path.iter()
+            .map(|cell| Point2::new((cell % grid.cols) as f32 * CELL_SIZE, (cell / grid.cols) as f32 * CELL_SIZE))
+            .collect())
+    }
+
+    // Where to put a powerup, one of the spawn points of the map if it has
+    // some that can be reached, anywhere free otherwise
+    pub fn random_powerup_spot(&self, size: (f32, f32), from: Point2<f32>) -> Option<Point2<f32>> {
+        let spots: Vec<Point2<f32>> = self.powerup_spawns.iter()
+            .map(|spawn| Point2::new(spawn.x - size.0 / 2.0, spawn.y - size.1 / 2.0))
+            .filter(|pos| self.is_free(Rect::new(pos.x, pos.y, size.0, size.1)))
+            .filter(|pos| self.is_reachable(from, Point2::new(pos.x + size.0 / 2.0, pos.y + size.1 / 2.0)))
+            .collect();
+
+        if spots.is_empty() {
+            self.random_free_spot(size, from)
+        } else {
+            Some(spots[game_rng(|rng| rng.gen_range(0..spots.len()))])
+        }
+    }
+
+    // A random spot where something of that size fits and that can be reached
+    // from the given position, if there's one
+    pub fn random_free_spot(&self, size: (f32, f32), from: Point2<f32>) -> Option<Point2<f32>> {
+        for _ in 0..50 {
+            let pos = game_rng(|rng| Point2::new(
+                rng.gen_range(0.0..self.width - size.0),
+                rng.gen_range(0.0..self.height - size.1)
+            ));
+            let center = Point2::new(pos.x + size.0 / 2.0, pos.y + size.1 / 2.0);
+            if self.is_free(Rect::new(pos.x, pos.y, size.0, size.1)) && self.is_reachable(from, center) {
+                return Some(pos);
             }
         }
 
-        Ok(())
+        None
     }
+}
 
-    pub fn get_width(&self) -> f32 {
-        self.width
+// Plain grass all over the map
+fn ground(width: f32, height: f32) -> GameResult<graphics::MeshBuilder> {
+    let mut ground = graphics::MeshBuilder::new();
+    ground.polygon(
+        graphics::DrawMode::fill(),
+        &[Point2::new(0.0, 0.0), Point2::new(0.0, height),
+        Point2::new(width, height), Point2::new(width, 0.0)],
+        graphics::Color::from_rgb(104, 159, 56)
+    )?;
+
+    Ok(ground)
+}
+
+// Sometimes the trees (or rocks, or ponds) close off a bit of the map, so remove the
+// ones around it until everything big enough to walk around in can be reached from the spawn
+fn open_pockets(width: f32, height: f32, obstacles: &mut Vec<Obstacle>, spawn: Point2<f32>) -> WalkGrid {
+    let mut walk_grid = WalkGrid::new(width, height, obstacles);
+
+    for _ in 0..20 {
+        let pockets = walk_grid.pockets(walk_grid.cell_of(spawn));
+        if pockets.is_empty() { break; }
+
+        let mut in_pocket = vec![false; walk_grid.blocked.len()];
+        for cell in pockets.iter().flatten() { in_pocket[*cell] = true; }
+        obstacles.retain(|obstacle| !obstacle.kind.blocks_walking()
+            || !walk_grid.cells_of(obstacle.get_hitbox(), 1).iter().any(|cell| in_pocket[*cell]));
+
+        walk_grid = WalkGrid::new(width, height, obstacles);
     }
 
-    pub fn get_height(&self) -> f32 {
-        self.height
+    walk_grid
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::tiled_map_of;
+
+    // What the editor saves is what the game plays on
+    #[test]
+    fn editor_maps_load_back() {
+        let obstacles = [
+            (0, Point2::new(16.0, 32.0), (24.0, 40.0)),
+            (2, Point2::new(120.0, 48.0), (16.0, 12.0)),
+            (4, Point2::new(200.0, 104.0), (32.0, 24.0)),
+            (5, Point2::new(8.0, 296.0), (24.0, 16.0))
+        ];
+        let spawns = [
+            (SpawnKind::Bertrand, Point2::new(40.0, 400.0)),
+            (SpawnKind::Fabien, Point2::new(320.0, 240.0)),
+            (SpawnKind::Powerup, Point2::new(500.0, 64.0)),
+            (SpawnKind::Bertrand, Point2::new(600.0, 16.0))
+        ];
+        let mut grass = vec![None; 40 * 30];
+        grass[0] = Some(1);
+        grass[41] = Some(4);
+        let tiled_map = tiled_map_of((40, 30), &grass, &obstacles, &spawns);
+
+        let json = serde_json::to_vec(&tiled_map).unwrap();
+        let loaded = TiledMap::from_json(&json[..], "/maps/test.json").unwrap();
+        let objects = TiledObjects::read(&loaded, "/maps/test.json");
+
+        assert_eq!(loaded.pixel_size(), (640.0, 480.0));
+        let kinds: Vec<(ObstacleKind, Point2<f32>)> = objects.obstacles.iter().map(|(kind, _, pos)| (*kind, *pos)).collect();
+        assert_eq!(kinds, vec![
+            (ObstacleKind::Tree, Point2::new(16.0, 32.0)),
+            (ObstacleKind::Rock, Point2::new(120.0, 48.0)),
+            (ObstacleKind::Water, Point2::new(200.0, 104.0)),
+            (ObstacleKind::Mud, Point2::new(8.0, 296.0))
+        ]);
+        let images: Vec<Option<String>> = objects.obstacles.iter().map(|(_, gid, _)| loaded.tile_image(*gid)).collect();
+        assert_eq!(images, vec![Some("/tree_0.png".to_string()), Some("/rock.png".to_string()),
+            Some("/water.png".to_string()), Some("/mud.png".to_string())]);
+
+        assert_eq!(objects.spawn, Point2::new(320.0, 240.0));
+        assert_eq!(objects.bertrand_spawns, vec![Point2::new(40.0, 400.0), Point2::new(600.0, 16.0)]);
+        assert_eq!(objects.powerup_spawns, vec![Point2::new(500.0, 64.0)]);
+        assert!(objects.warnings.is_empty());
+
+        let tiles: Vec<(Option<String>, Point2<f32>)> = loaded.tiles().into_iter()
+            .map(|(gid, pos)| (loaded.tile_image(gid), pos))
+            .collect();
+        assert_eq!(tiles, vec![
+            (Some("/grass_1.png".to_string()), Point2::new(0.0, 0.0)),
+            (Some("/grass_4.png".to_string()), Point2::new(16.0, 16.0))
+        ]);
     }
 
-    pub fn get_trees(&mut self) -> &mut Vec<Tree> {
-        &mut self.trees
+    #[test]
+    fn unknown_spawns_are_reported() {
+        let json = r#"{ "width": 10, "height": 10, "tilewidth": 16, "tileheight": 16, "tilesets": [],
+            "layers": [ { "type": "objectgroup", "objects": [
+                { "name": "bertrand", "type": "spawn", "x": 40.0, "y": 24.0, "point": true },
+                { "name": "dragon", "type": "spawn", "x": 10.0, "y": 10.0, "point": true }
+            ] } ] }"#;
+
+        let loaded = TiledMap::from_json(json.as_bytes(), "/maps/test.json").unwrap();
+        let objects = TiledObjects::read(&loaded, "/maps/test.json");
+        assert_eq!(objects.bertrand_spawns, vec![Point2::new(40.0, 24.0)]);
+        assert_eq!(objects.warnings.len(), 1);
+        // Fabien starts in the middle when the map doesn't say
+        assert_eq!(objects.spawn, Point2::new(80.0, 80.0));
     }
 }
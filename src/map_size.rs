@@ -0,0 +1,54 @@
+// How big the random maps are. The trees and the grass are spread according
+// to the area, the densities only say how thick the forest is on top of that.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MapSize {
+    Small,
+    Medium,
+    Huge
+}
+
+impl MapSize {
+    // The one after this one, to cycle through them in the settings
+    pub fn next(&self) -> MapSize {
+        match self {
+            MapSize::Small => MapSize::Medium,
+            MapSize::Medium => MapSize::Huge,
+            MapSize::Huge => MapSize::Small
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapSize::Small => "Petite arène",
+            MapSize::Medium => "Moyenne",
+            MapSize::Huge => "Immense"
+        }
+    }
+
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            MapSize::Small => (1000.0, 1000.0),
+            MapSize::Medium => (2000.0, 2000.0),
+            MapSize::Huge => (3500.0, 3500.0)
+        }
+    }
+
+    // Multiplies the chance of a tree growing anywhere, the small arena is
+    // more open so that there's still room to run around
+    pub fn tree_density(&self) -> f32 {
+        match self {
+            MapSize::Small => 0.6,
+            MapSize::Medium => 1.0,
+            MapSize::Huge => 1.2
+        }
+    }
+
+    // Same for the grass, which is just for the looks
+    pub fn grass_density(&self) -> f32 {
+        match self {
+            MapSize::Small => 1.2,
+            MapSize::Medium => 1.0,
+            MapSize::Huge => 0.8
+        }
+    }
+}
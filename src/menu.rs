@@ -1,12 +1,18 @@
 use ggez::{
-    GameResult, Context,
+    GameResult, Context, filesystem,
     graphics::Color,
     nalgebra::Point2,
     input::mouse::MouseButton
 };
-use crate::utils::loading_screen;
+use crate::utils::{ loading_screen, STATS_KEYS };
 use crate::text::Text;
 use crate::button::Button;
+use crate::difficulty::Difficulty;
+use crate::map_size::MapSize;
+use crate::minimap::MinimapSize;
+use crate::lighting::LightingQuality;
+use crate::ui::UiScale;
+use crate::game_mode::GameMode;
 use std::collections::HashMap;
 use serde::Deserialize;
 use urlencoding::encode;
@@ -41,43 +47,170 @@ enum ButtonType {
     Play,
     Stats,
     Settings,
-    Quit
+    Quit,
+    Difficulty,
+    Director,
+    Mode,
+    Map,
+    MapSize,
+    Minimap,
+    Lighting,
+    UiScale,
+    Daily,
+    Editor
 }
 
 pub struct Menu {
     state: MenuState,
     texts: HashMap<String, Text>,
-    buttons: HashMap<ButtonType, Button>
+    buttons: HashMap<ButtonType, Button>,
+    difficulty: Difficulty,
+    director_enabled: bool,
+    mode: GameMode,
+    // The hand-made maps in resources/maps, and which one is picked (None for a random map)
+    maps: Vec<String>,
+    map_index: Option<usize>,
+    // Only for the random maps
+    map_size: MapSize,
+    minimap_size: MinimapSize,
+    lighting: LightingQuality,
+    ui_scale: UiScale
 }
 
-const BUTTON_WIDTH: f32 = 400.0;
-const BUTTON_HEIGHT: f32 = 100.0;
-const SPACING: f32 = 40.0;
+// On the UI canvas, so they're as big on any window
+const BUTTON_WIDTH: f32 = 360.0;
+const BUTTON_HEIGHT: f32 = 80.0;
+const SPACING: f32 = 30.0;
+
+const NB_SETTINGS: usize = 8;
+// Play and Options, Statistiques and Quitter, then the daily challenge and the editor
+const MAIN_ROWS: usize = 3;
+
+// The main menu's buttons, on two columns and from the bottom of the screen up,
+// the title and the notice take what's left above them
+fn main_button_pos(row: usize, column: usize, canvas_size: (f32, f32)) -> (f32, f32) {
+    let x = match column {
+        0 => canvas_size.0 / 2.0 - BUTTON_WIDTH - SPACING,
+        _ => canvas_size.0 / 2.0 + SPACING
+    };
+    let y = canvas_size.1 - SPACING - (MAIN_ROWS - row) as f32 * BUTTON_HEIGHT
+        - (MAIN_ROWS - row - 1) as f32 * SPACING / 2.0;
+
+    (x, y)
+}
+
+// At the bottom of the screen, under the settings or the stats
+fn back_button_pos(canvas_size: (f32, f32)) -> (f32, f32) {
+    (canvas_size.0 / 2.0 - BUTTON_WIDTH / 2.0, canvas_size.1 - BUTTON_HEIGHT - SPACING)
+}
+
+// The settings are buttons that cycle through their values when clicked,
+// laid out on two columns in the space left above Back
+fn settings_button_pos(index: usize, canvas_size: (f32, f32)) -> (f32, f32) {
+    let x = match index % 2 {
+        0 => canvas_size.0 / 2.0 - BUTTON_WIDTH - SPACING,
+        _ => canvas_size.0 / 2.0 + SPACING
+    };
+
+    let rows = NB_SETTINGS.div_ceil(2);
+    let space = back_button_pos(canvas_size).1 - SPACING / 2.0;
+    // The rows get closer when the canvas is too small for them
+    let step = ((space - BUTTON_HEIGHT) / (rows - 1) as f32).min(BUTTON_HEIGHT + SPACING / 2.0);
+    let top = ((space - BUTTON_HEIGHT - (rows - 1) as f32 * step) / 2.0).max(0.0);
+    let y = top + (index / 2) as f32 * step;
+
+    (x, y)
+}
+
+// The Tiled maps (saved as .tmx or JSON) in resources/maps
+fn list_maps(ctx: &mut Context) -> Vec<String> {
+    let mut maps: Vec<String> = match filesystem::read_dir(ctx, "/maps") {
+        Ok(files) => files
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json" || ext == "tmx"))
+            .filter_map(|path| path.to_str().map(|path| path.replace('\\', "/")))
+            .collect(),
+        Err(_) => vec![]
+    };
+    maps.sort();
+    maps.dedup();
+
+    maps
+}
+
+// "/maps/arena.json" (or .tmx) is shown as "arena"
+fn map_name(path: &str) -> &str {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name.strip_suffix(".json").or_else(|| file_name.strip_suffix(".tmx")).unwrap_or(file_name)
+}
 
 impl Menu {
-    pub fn new(ctx: &mut Context, screen_size: (f32, f32)) -> GameResult<Menu> {
+    pub fn new(ctx: &mut Context, canvas_size: (f32, f32)) -> GameResult<Menu> {
         let font_path = "/Fonts/arial_narrow_7.ttf".to_string();
 
-        let mut title_text = Text::new(ctx, String::from("B-Hunt"), font_path.clone(), 200.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
-        title_text.set_pos(Point2::new(screen_size.0 / 2.0 - title_text.width(ctx) / 2.0,
-                screen_size.1 / 3.5 - title_text.height(ctx) / 2.0));
+        let title_text = Text::new(ctx, String::from("B-Hunt"), font_path.clone(), 150.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
 
-        let mut stats_text = Text::new(ctx, String::from(""), font_path.clone(), 40.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
-        stats_text.set_pos(Point2::new(100.0, 100.0));
+        let mut stats_text = Text::new(ctx, String::from(""), font_path.clone(), 30.0, Color::new(1.0, 1.0, 1.0, 1.0))?;
+        stats_text.set_pos(Point2::new(SPACING, SPACING));
+
+        // Under the title, when something went wrong with the map
+        let notice_text = Text::new(ctx, String::from(""), font_path.clone(), 32.0, Color::from_rgb(255, 150, 90))?;
 
         let color_not_hover = Color::from_rgb(255, 255, 255);
         let color_hover = Color::from_rgb(160, 160, 160);
 
-        let play_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, screen_size.0 / 2.0 - BUTTON_WIDTH - SPACING,
-            screen_size.1 / 1.75 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 10.0, "Jouer".to_string())?;
-        let stats_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, screen_size.0 / 4.0 - BUTTON_WIDTH - SPACING,
-            screen_size.1 / 1.3 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 10.0,  "Statistiques".to_string())?;
-        let back_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, screen_size.0 / 2.0 - BUTTON_WIDTH / 2.0,
-            screen_size.1 / 1.3 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 10.0, "Retour".to_string())?;
-        let quit_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, screen_size.0 / 2.0 + SPACING,
-            screen_size.1 / 1.3 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 10.0, "Quitter".to_string())?;
-        let set_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, screen_size.0 / 2.0 + SPACING,
-            screen_size.1 / 1.75 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 10.0, "Options".to_string())?;
+        let pos = main_button_pos(0, 0, canvas_size);
+        let play_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Jouer".to_string())?;
+        let pos = main_button_pos(1, 0, canvas_size);
+        let stats_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Statistiques".to_string())?;
+        let pos = back_button_pos(canvas_size);
+        let back_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Retour".to_string())?;
+        let pos = main_button_pos(1, 1, canvas_size);
+        let quit_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Quitter".to_string())?;
+        let pos = main_button_pos(0, 1, canvas_size);
+        let set_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Options".to_string())?;
+        let pos = main_button_pos(2, 0, canvas_size);
+        let daily_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Défi du jour".to_string())?;
+        let pos = main_button_pos(2, 1, canvas_size);
+        let editor_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Éditeur".to_string())?;
+
+        let difficulty = Difficulty::Normal;
+        let pos = settings_button_pos(0, canvas_size);
+        let difficulty_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, format!("Difficulté : {}", difficulty.name()))?;
+        let pos = settings_button_pos(1, canvas_size);
+        let director_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Directeur : Désactivé".to_string())?;
+        let mode = GameMode::Survival;
+        let pos = settings_button_pos(2, canvas_size);
+        let mode_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, format!("Mode : {}", mode.name()))?;
+        let maps = list_maps(ctx);
+        let pos = settings_button_pos(3, canvas_size);
+        let map_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, "Carte : Aléatoire".to_string())?;
+        let map_size = MapSize::Medium;
+        let pos = settings_button_pos(4, canvas_size);
+        let map_size_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, format!("Taille : {}", map_size.name()))?;
+        let minimap_size = MinimapSize::Medium;
+        let pos = settings_button_pos(5, canvas_size);
+        let minimap_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, format!("Minicarte : {}", minimap_size.name()))?;
+        let lighting = LightingQuality::Full;
+        let pos = settings_button_pos(6, canvas_size);
+        let lighting_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, format!("Éclairage : {}", lighting.name()))?;
+        let ui_scale = UiScale::Normal;
+        let pos = settings_button_pos(7, canvas_size);
+        let ui_scale_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.0, pos.1, color_not_hover,
+            color_hover, 10.0, format!("Interface : {}", ui_scale.name()))?;
 
         let mut buttons = HashMap::new();
         buttons.insert(ButtonType::Play, play_button);
@@ -85,16 +218,37 @@ impl Menu {
         buttons.insert(ButtonType::Back, back_button);
         buttons.insert(ButtonType::Quit, quit_button);
         buttons.insert(ButtonType::Settings, set_button);
+        buttons.insert(ButtonType::Daily, daily_button);
+        buttons.insert(ButtonType::Editor, editor_button);
+        buttons.insert(ButtonType::Difficulty, difficulty_button);
+        buttons.insert(ButtonType::Director, director_button);
+        buttons.insert(ButtonType::Mode, mode_button);
+        buttons.insert(ButtonType::Map, map_button);
+        buttons.insert(ButtonType::MapSize, map_size_button);
+        buttons.insert(ButtonType::Minimap, minimap_button);
+        buttons.insert(ButtonType::Lighting, lighting_button);
+        buttons.insert(ButtonType::UiScale, ui_scale_button);
 
         let mut texts = HashMap::new();
         texts.insert("title".to_string(), title_text);
         texts.insert("stats".to_string(), stats_text);
+        texts.insert("notice".to_string(), notice_text);
 
-        let menu = Menu {
+        let mut menu = Menu {
             state: MenuState::Main,
             texts: texts,
-            buttons: buttons
+            buttons: buttons,
+            difficulty: difficulty,
+            director_enabled: false,
+            mode: mode,
+            maps: maps,
+            map_index: None,
+            map_size: map_size,
+            minimap_size: minimap_size,
+            lighting: lighting,
+            ui_scale: ui_scale
         };
+        menu.place_title(ctx, canvas_size);
 
         Ok(menu)
     }
@@ -107,10 +261,13 @@ impl Menu {
         match self.state {
             MenuState::Main => {
                 self.texts[&"title".to_string()].draw(ctx)?; 
+                self.texts[&"notice".to_string()].draw(ctx)?;
                 self.buttons[&ButtonType::Play].draw(ctx)?;
                 self.buttons[&ButtonType::Stats].draw(ctx)?;
                 self.buttons[&ButtonType::Quit].draw(ctx)?;
                 self.buttons[&ButtonType::Settings].draw(ctx)?;
+                self.buttons[&ButtonType::Daily].draw(ctx)?;
+                self.buttons[&ButtonType::Editor].draw(ctx)?;
             },
             MenuState::Stats => {
                 self.buttons[&ButtonType::Back].draw(ctx)?;
@@ -118,6 +275,14 @@ impl Menu {
             },
             MenuState::Settings => {
                 self.buttons[&ButtonType::Back].draw(ctx)?;
+                self.buttons[&ButtonType::Difficulty].draw(ctx)?;
+                self.buttons[&ButtonType::Director].draw(ctx)?;
+                self.buttons[&ButtonType::Mode].draw(ctx)?;
+                self.buttons[&ButtonType::Map].draw(ctx)?;
+                self.buttons[&ButtonType::MapSize].draw(ctx)?;
+                self.buttons[&ButtonType::Minimap].draw(ctx)?;
+                self.buttons[&ButtonType::Lighting].draw(ctx)?;
+                self.buttons[&ButtonType::UiScale].draw(ctx)?;
             }
         }
 
@@ -133,6 +298,7 @@ impl Menu {
     pub fn mouse_button_down_event(&mut self, ctx: &mut Context, x: f32, y: f32,
         mouse_button: MouseButton, screen_size: (f32, f32)) -> u8
     {
+        let mut clicked_setting = None;
         if let MouseButton::Left = mouse_button {
             for (which, button) in self.buttons.iter() {
                 if button.contains(x, y) {
@@ -143,6 +309,11 @@ impl Menu {
                                     self.texts.get_mut(&"stats".to_string()).unwrap().set_string(String::from(""));
                                     return 1;
                                 },
+                                ButtonType::Daily => {
+                                    self.texts.get_mut(&"stats".to_string()).unwrap().set_string(String::from(""));
+                                    return 2;
+                                },
+                                ButtonType::Editor => return 3,
                                 ButtonType::Stats => {
                                     if self.texts[&"stats".to_string()].contents() == "" {
                                         loading_screen(ctx, screen_size);
@@ -156,11 +327,12 @@ impl Menu {
                                 },
                                 ButtonType::Settings => {
                                     self.state = MenuState::Settings;
+                                    break;
                                 }
                                 _ => {}
                             }
                         },
-                        MenuState::Stats | MenuState::Settings => {
+                        MenuState::Stats => {
                             match which {
                                 ButtonType::Back => {
                                     self.state = MenuState::Main;
@@ -168,15 +340,178 @@ impl Menu {
                                 },
                                 _ => {}
                             }
+                        },
+                        MenuState::Settings => {
+                            match which {
+                                ButtonType::Back => {
+                                    self.state = MenuState::Main;
+                                    break;
+                                },
+                                ButtonType::Difficulty => {
+                                    clicked_setting = Some(ButtonType::Difficulty);
+                                    break;
+                                },
+                                ButtonType::Director => {
+                                    clicked_setting = Some(ButtonType::Director);
+                                    break;
+                                },
+                                ButtonType::Mode => {
+                                    clicked_setting = Some(ButtonType::Mode);
+                                    break;
+                                },
+                                ButtonType::Map => {
+                                    clicked_setting = Some(ButtonType::Map);
+                                    break;
+                                },
+                                ButtonType::MapSize => {
+                                    clicked_setting = Some(ButtonType::MapSize);
+                                    break;
+                                },
+                                ButtonType::Minimap => {
+                                    clicked_setting = Some(ButtonType::Minimap);
+                                    break;
+                                },
+                                ButtonType::Lighting => {
+                                    clicked_setting = Some(ButtonType::Lighting);
+                                    break;
+                                },
+                                ButtonType::UiScale => {
+                                    clicked_setting = Some(ButtonType::UiScale);
+                                    break;
+                                },
+                                _ => {}
+                            }
                         }
                     }
                 }
             }
         }
 
+        // Can't change the buttons while going through them
+        if let Some(setting) = clicked_setting {
+            self.cycle_setting(ctx, setting);
+        }
+
         0
     }
 
+    fn cycle_setting(&mut self, ctx: &mut Context, setting: ButtonType) {
+        let text = match setting {
+            ButtonType::Difficulty => {
+                self.difficulty = self.difficulty.next();
+                format!("Difficulté : {}", self.difficulty.name())
+            },
+            ButtonType::Director => {
+                self.director_enabled = !self.director_enabled;
+                format!("Directeur : {}", if self.director_enabled { "Activé" } else { "Désactivé" })
+            },
+            ButtonType::Mode => {
+                self.mode = self.mode.next();
+                // Each mode has its own stats, they'll have to be fetched again
+                self.texts.get_mut(&"stats".to_string()).unwrap().set_string(String::from(""));
+                format!("Mode : {}", self.mode.name())
+            },
+            ButtonType::Map => {
+                // Random first, then each of the maps
+                self.map_index = match self.map_index {
+                    None if !self.maps.is_empty() => Some(0),
+                    Some(i) if i + 1 < self.maps.len() => Some(i + 1),
+                    _ => None
+                };
+                self.map_label()
+            },
+            ButtonType::MapSize => {
+                self.map_size = self.map_size.next();
+                format!("Taille : {}", self.map_size.name())
+            },
+            ButtonType::Minimap => {
+                self.minimap_size = self.minimap_size.next();
+                format!("Minicarte : {}", self.minimap_size.name())
+            },
+            ButtonType::Lighting => {
+                self.lighting = self.lighting.next();
+                format!("Éclairage : {}", self.lighting.name())
+            },
+            ButtonType::UiScale => {
+                self.ui_scale = self.ui_scale.next();
+                format!("Interface : {}", self.ui_scale.name())
+            },
+            _ => return
+        };
+
+        self.buttons.get_mut(&setting).unwrap().set_text(ctx, text);
+    }
+
+    pub fn get_difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn is_director_enabled(&self) -> bool {
+        self.director_enabled
+    }
+
+    pub fn get_mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn get_map_size(&self) -> MapSize {
+        self.map_size
+    }
+
+    pub fn get_minimap_size(&self) -> MinimapSize {
+        self.minimap_size
+    }
+
+    pub fn get_lighting(&self) -> LightingQuality {
+        self.lighting
+    }
+
+    pub fn get_ui_scale(&self) -> UiScale {
+        self.ui_scale
+    }
+
+    pub fn get_map(&self) -> Option<String> {
+        self.map_index.map(|i| self.maps[i].clone())
+    }
+
+    // The editor may have saved a new one, the picked map stays picked
+    pub fn refresh_maps(&mut self, ctx: &mut Context) {
+        let picked = self.get_map();
+        self.maps = list_maps(ctx);
+        self.map_index = picked.and_then(|picked| self.maps.iter().position(|path| *path == picked));
+        let text = self.map_label();
+        self.buttons.get_mut(&ButtonType::Map).unwrap().set_text(ctx, text);
+    }
+
+    fn map_label(&self) -> String {
+        format!("Carte : {}", self.map_index.map_or("Aléatoire", |i| map_name(&self.maps[i])))
+    }
+
+    // Empty to hide it
+    pub fn set_notice(&mut self, ctx: &mut Context, notice: String, canvas_size: (f32, f32)) {
+        self.texts.get_mut(&"notice".to_string()).unwrap().set_string(notice);
+        self.place_title(ctx, canvas_size);
+    }
+
+    // The title and the notice under it, in the middle of what the buttons leave
+    fn place_title(&mut self, ctx: &mut Context, canvas_size: (f32, f32)) {
+        let notice_height = self.texts[&"notice".to_string()].height(ctx);
+        let title_text = self.texts.get_mut(&"title".to_string()).unwrap();
+        let space = main_button_pos(0, 0, canvas_size).1;
+        let title_y = ((space - title_text.height(ctx) - notice_height) / 2.0).max(0.0);
+        title_text.set_pos(Point2::new(canvas_size.0 / 2.0 - title_text.width(ctx) / 2.0, title_y));
+        let notice_y = title_y + title_text.height(ctx);
+
+        let notice_text = self.texts.get_mut(&"notice".to_string()).unwrap();
+        notice_text.set_pos(Point2::new(canvas_size.0 / 2.0 - notice_text.width(ctx) / 2.0, notice_y));
+    }
+
+    // Tell the player the daily challenge won't count anymore today
+    pub fn set_daily_played(&mut self, ctx: &mut Context, played: bool) {
+        let text = if played { "Défi du jour (essai)" } else { "Défi du jour" };
+        self.buttons.get_mut(&ButtonType::Daily).unwrap().set_text(ctx, text.to_string());
+    }
+
     fn get_stats(&mut self) {
         let mut error_message = String::from("");
         // Get the user info in the .gj_credentials file
@@ -207,14 +542,13 @@ impl Menu {
             Err(_) => error_message = String::from("Variable manquante (PRIVATE_KEY)")
         }
 
-        let keys: [&str; 6] = ["bertrand_killed", "shots", "powerups_activated",
-                               "hits_taken", "time_played", "games_played"];
+        let keys = STATS_KEYS;
 
         let mut api_url = format!("https://api.gamejolt.com/api/game/v1_2/batch?game_id={}", game_id);
 
         for key in keys.iter() {
-            let mut url = format!("/data-store/?game_id={}&key={}&username={}&user_token={}",
-                                    game_id, key, username, user_token);
+            let mut url = format!("/data-store/?game_id={}&key={}{}&username={}&user_token={}",
+                                    game_id, self.mode.stats_prefix(), key, username, user_token);
             let mut hasher = sha1::Sha1::new();
             hasher.update(format!("{}{}", url, private_key).as_bytes());
             let signature = hasher.digest().to_string();
@@ -245,7 +579,7 @@ impl Menu {
         if error_message != "" {
             stats = error_message;
         } else {
-            stats = format!("Statistiques pour {} :\n\n", username);
+            stats = format!("Statistiques pour {} ({}) :\n\n", username, self.mode.name());
             for (i, data) in response.response.responses.iter().enumerate() {
                 if i >= keys.len() { break; }
 
@@ -256,6 +590,11 @@ impl Menu {
                     "hits_taken" => "Nombre de coups pris",
                     "time_played" => "Temps joué (en secondes)",
                     "games_played" => "Nombre de parties jouées",
+                    "hits_absorbed" => "Nombre de coups bloqués par un bouclier",
+                    "time_freezes" => "Nombre de gels du temps",
+                    "multishots" => "Nombre de tirs multiples activés",
+                    "magnets" => "Nombre d'aimants activés",
+                    "explosion_kills" => "Nombre d'ennemies tués par une explosion",
                     _ => unreachable!()
                 };
 
@@ -269,26 +608,69 @@ impl Menu {
     }
 
     pub fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
-        let title_text = self.texts.get_mut(&"title".to_string()).unwrap();
-        title_text.set_pos(Point2::new(width / 2.0 - title_text.width(ctx) / 2.0,
-                height / 3.5 - title_text.height(ctx) / 2.0));
+        self.place_title(ctx, (width, height));
 
         let stats_text = self.texts.get_mut(&"stats".to_string()).unwrap();
-        stats_text.set_pos(Point2::new(100.0, 100.0));
+        stats_text.set_pos(Point2::new(SPACING, SPACING));
 
+        let pos = main_button_pos(0, 0, (width, height));
         let play_button = self.buttons.get_mut(&ButtonType::Play).unwrap();
-        play_button.set_pos(ctx, width / 2.0 - BUTTON_WIDTH - SPACING, height / 1.75 - BUTTON_HEIGHT / 2.0);
+        play_button.set_pos(ctx, pos.0, pos.1);
 
+        let pos = main_button_pos(1, 0, (width, height));
         let stats_button = self.buttons.get_mut(&ButtonType::Stats).unwrap();
-        stats_button.set_pos(ctx, width / 2.0 - BUTTON_WIDTH - SPACING, height / 1.3 - BUTTON_HEIGHT / 2.0);
+        stats_button.set_pos(ctx, pos.0, pos.1);
 
+        let pos = back_button_pos((width, height));
         let back_button = self.buttons.get_mut(&ButtonType::Back).unwrap();
-        back_button.set_pos(ctx, width / 2.0 - BUTTON_WIDTH / 2.0, height / 1.3 - BUTTON_HEIGHT / 2.0);
+        back_button.set_pos(ctx, pos.0, pos.1);
 
+        let pos = main_button_pos(0, 1, (width, height));
         let set_button = self.buttons.get_mut(&ButtonType::Settings).unwrap();
-        set_button.set_pos(ctx, width / 2.0 + SPACING, height / 1.75 - BUTTON_HEIGHT / 2.0);
+        set_button.set_pos(ctx, pos.0, pos.1);
 
+        let pos = main_button_pos(1, 1, (width, height));
         let quit_button = self.buttons.get_mut(&ButtonType::Quit).unwrap();
-        quit_button.set_pos(ctx, width / 2.0 + SPACING, height / 1.3 - BUTTON_HEIGHT / 2.0);
+        quit_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = main_button_pos(2, 0, (width, height));
+        let daily_button = self.buttons.get_mut(&ButtonType::Daily).unwrap();
+        daily_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = main_button_pos(2, 1, (width, height));
+        let editor_button = self.buttons.get_mut(&ButtonType::Editor).unwrap();
+        editor_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(0, (width, height));
+        let difficulty_button = self.buttons.get_mut(&ButtonType::Difficulty).unwrap();
+        difficulty_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(1, (width, height));
+        let director_button = self.buttons.get_mut(&ButtonType::Director).unwrap();
+        director_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(2, (width, height));
+        let mode_button = self.buttons.get_mut(&ButtonType::Mode).unwrap();
+        mode_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(3, (width, height));
+        let map_button = self.buttons.get_mut(&ButtonType::Map).unwrap();
+        map_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(4, (width, height));
+        let map_size_button = self.buttons.get_mut(&ButtonType::MapSize).unwrap();
+        map_size_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(5, (width, height));
+        let minimap_button = self.buttons.get_mut(&ButtonType::Minimap).unwrap();
+        minimap_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(6, (width, height));
+        let lighting_button = self.buttons.get_mut(&ButtonType::Lighting).unwrap();
+        lighting_button.set_pos(ctx, pos.0, pos.1);
+
+        let pos = settings_button_pos(7, (width, height));
+        let ui_scale_button = self.buttons.get_mut(&ButtonType::UiScale).unwrap();
+        ui_scale_button.set_pos(ctx, pos.0, pos.1);
     }
 }
@@ -0,0 +1,190 @@
+use ggez::{
+    graphics, Context, GameResult,
+    graphics::{ Color, Rect },
+    nalgebra::{ Point2, Vector2 }
+};
+
+use crate::map::Map;
+use crate::terrain::ObstacleKind;
+use crate::ui::{ Ui, Anchor };
+
+// Only the Bertrands that close to Fabien show up, the others are too far to hear
+const RADAR_RADIUS: f32 = 500.0;
+// Space left above it for the health
+const TOP_MARGIN: f32 = 60.0;
+// Size of the dots, in UI units (like the rest of the HUD)
+const DOT_RADIUS: f32 = 2.7;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MinimapSize {
+    Hidden,
+    Small,
+    Medium,
+    Large
+}
+
+impl MinimapSize {
+    // The one after this one, to cycle through them in the settings
+    pub fn next(&self) -> MinimapSize {
+        match self {
+            MinimapSize::Hidden => MinimapSize::Small,
+            MinimapSize::Small => MinimapSize::Medium,
+            MinimapSize::Medium => MinimapSize::Large,
+            MinimapSize::Large => MinimapSize::Hidden
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MinimapSize::Hidden => "Cachée",
+            MinimapSize::Small => "Petite",
+            MinimapSize::Medium => "Moyenne",
+            MinimapSize::Large => "Grande"
+        }
+    }
+
+    // How much of the height of the screen it takes
+    fn fraction(&self) -> Option<f32> {
+        match self {
+            MinimapSize::Hidden => None,
+            MinimapSize::Small => Some(0.2),
+            MinimapSize::Medium => Some(0.3),
+            MinimapSize::Large => Some(0.45)
+        }
+    }
+
+    fn bigger(&self) -> MinimapSize {
+        match self {
+            MinimapSize::Small => MinimapSize::Medium,
+            _ => MinimapSize::Large
+        }
+    }
+
+    fn smaller(&self) -> MinimapSize {
+        match self {
+            MinimapSize::Large => MinimapSize::Medium,
+            _ => MinimapSize::Small
+        }
+    }
+}
+
+fn terrain_color(kind: ObstacleKind) -> Color {
+    match kind {
+        ObstacleKind::Tree => Color::from_rgb(30, 75, 30),
+        ObstacleKind::Rock => Color::from_rgb(130, 130, 135),
+        ObstacleKind::Water => Color::from_rgb(52, 110, 190),
+        ObstacleKind::Bush => Color::from_rgb(60, 120, 45),
+        ObstacleKind::Mud => Color::from_rgb(110, 80, 50)
+    }
+}
+
+// The map in a corner of the screen, with Fabien, the Bertrands around him,
+// the powerups and what the camera sees
+pub struct Minimap {
+    // The obstacles, in map units, drawn once for each map
+    terrain: graphics::Mesh,
+    map_size: (f32, f32),
+    size: MinimapSize,
+    // To bring it back at the same size when it's toggled
+    shown_size: MinimapSize
+}
+
+impl Minimap {
+    pub fn new(ctx: &mut Context, map: &Map, size: MinimapSize) -> GameResult<Minimap> {
+        let minimap = Minimap {
+            terrain: Minimap::draw_terrain(ctx, map)?,
+            map_size: (map.get_width(), map.get_height()),
+            size: size,
+            shown_size: if size == MinimapSize::Hidden { MinimapSize::Medium } else { size }
+        };
+
+        Ok(minimap)
+    }
+
+    fn draw_terrain(ctx: &mut Context, map: &Map) -> GameResult<graphics::Mesh> {
+        let mut terrain = graphics::MeshBuilder::new();
+        terrain.rectangle(graphics::DrawMode::fill(), Rect::new(0.0, 0.0, map.get_width(), map.get_height()),
+            Color::from_rgb(104, 159, 56));
+        // Flat things first so that the trees are on top
+        let (flat, standing): (Vec<_>, Vec<_>) = map.get_obstacles().iter().partition(|obstacle| obstacle.get_kind().is_flat());
+        for obstacle in flat.iter().chain(standing.iter()) {
+            terrain.rectangle(graphics::DrawMode::fill(), obstacle.get_sight_box(), terrain_color(obstacle.get_kind()));
+        }
+
+        terrain.build(ctx)
+    }
+
+    // When the map changes, or something on it broke
+    pub fn rebuild(&mut self, ctx: &mut Context, map: &Map) -> GameResult {
+        self.terrain = Minimap::draw_terrain(ctx, map)?;
+        self.map_size = (map.get_width(), map.get_height());
+
+        Ok(())
+    }
+
+    pub fn set_size(&mut self, size: MinimapSize) {
+        self.size = size;
+        if size != MinimapSize::Hidden { self.shown_size = size; }
+    }
+
+    pub fn toggle(&mut self) {
+        self.size = if self.size == MinimapSize::Hidden { self.shown_size } else { MinimapSize::Hidden };
+    }
+
+    pub fn grow(&mut self) {
+        self.set_size(self.size.bigger());
+    }
+
+    pub fn shrink(&mut self) {
+        self.set_size(self.size.smaller());
+    }
+
+    // In the top right corner of the screen, under the health. The camera is
+    // only there to show what it sees.
+    pub fn draw(&self, ctx: &mut Context, ui: &Ui, camera: Rect, fabien: Rect, bertrands: &[Rect], powerups: &[Rect]) -> GameResult {
+        let fraction = match self.size.fraction() {
+            Some(fraction) => fraction,
+            None => return Ok(())
+        };
+
+        let height = ui.area().h * fraction;
+        let scale = height / self.map_size.1;
+        let width = self.map_size.0 * scale;
+        let corner = ui.place(Anchor::TopRight, (width, height), (3.0, TOP_MARGIN));
+        let frame = Rect::new(corner.x, corner.y, width, height);
+        let to_minimap = |x: f32, y: f32| Point2::new(frame.x + x * scale, frame.y + y * scale);
+        let middle = |rect: &Rect| to_minimap(rect.x + rect.w / 2.0, rect.y + rect.h / 2.0);
+
+        let border = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(),
+            Rect::new(frame.x - 1.5, frame.y - 1.5, frame.w + 3.0, frame.h + 3.0), Color::new(0.0, 0.0, 0.0, 0.6))?;
+        graphics::draw(ctx, &border, graphics::DrawParam::default())?;
+        graphics::draw(ctx, &self.terrain, graphics::DrawParam::new()
+            .dest(Point2::new(frame.x, frame.y))
+            .scale(Vector2::new(scale, scale))
+            .color(Color::new(1.0, 1.0, 1.0, 0.85)))?;
+
+        let mut overlay = graphics::MeshBuilder::new();
+        let view_corner = to_minimap(camera.x.max(0.0), camera.y.max(0.0));
+        let view_end = to_minimap((camera.x + camera.w).min(self.map_size.0), (camera.y + camera.h).min(self.map_size.1));
+        overlay.rectangle(graphics::DrawMode::stroke(1.2),
+            Rect::new(view_corner.x, view_corner.y, view_end.x - view_corner.x, view_end.y - view_corner.y),
+            graphics::WHITE);
+
+        for powerup in powerups.iter() {
+            overlay.circle(graphics::DrawMode::fill(), middle(powerup), DOT_RADIUS, 0.3, Color::from_rgb(240, 200, 40));
+        }
+
+        let fabien_pos = Point2::new(fabien.x + fabien.w / 2.0, fabien.y + fabien.h / 2.0);
+        for bertrand in bertrands.iter() {
+            let pos = Point2::new(bertrand.x + bertrand.w / 2.0, bertrand.y + bertrand.h / 2.0);
+            if (pos - fabien_pos).norm() > RADAR_RADIUS { continue; }
+            overlay.circle(graphics::DrawMode::fill(), middle(bertrand), DOT_RADIUS, 0.3, Color::from_rgb(220, 50, 50));
+        }
+
+        overlay.circle(graphics::DrawMode::fill(), middle(&fabien), DOT_RADIUS * 1.3, 0.3, Color::from_rgb(66, 135, 245));
+        overlay.circle(graphics::DrawMode::stroke(0.9), middle(&fabien), DOT_RADIUS * 1.3, 0.3, graphics::WHITE);
+
+        let overlay = overlay.build(ctx)?;
+        graphics::draw(ctx, &overlay, graphics::DrawParam::default())
+    }
+}
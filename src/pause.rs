@@ -1,11 +1,12 @@
 use ggez::{
     Context, GameResult,
     graphics,
-    graphics::{ Color, Rect },
+    graphics::Color,
     nalgebra::{ Vector2, Point2 },
     input::mouse::MouseButton
 };
 use crate::button::Button;
+use crate::ui::{ Ui, Anchor };
 use std::collections::HashMap;
 
 #[derive(PartialEq, Eq, Hash)]
@@ -19,23 +20,30 @@ pub struct Pause {
     buttons: HashMap<ButtonType, Button>
 }
 
-const BUTTON_WIDTH: f32 = 100.0;
-const BUTTON_HEIGHT: f32 = 25.0;
+// In UI units, see Ui
+const BUTTON_WIDTH: f32 = 300.0;
+const BUTTON_HEIGHT: f32 = 75.0;
+const IMAGE_SCALE: f32 = 4.5;
+// How far below the middle of the screen things are, in screen heights
+const IMAGE_OFFSET: f32 = -0.21;
+const BACK_OFFSET: f32 = 0.07;
+const GIVE_UP_OFFSET: f32 = 0.27;
 
 impl Pause {
-    pub fn new(ctx: &mut Context, camera: Rect) -> GameResult<Pause> {
-        // graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, camera.w, camera.h))?;
+    pub fn new(ctx: &mut Context, ui: &Ui) -> GameResult<Pause> {
         let pause_image = graphics::Image::new(ctx, "/pause.png")?;
 
         let color_not_hover = Color::from_rgb(255, 255, 255);
         let color_hover = Color::from_rgb(160, 160, 160);
 
-        let mut back_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, camera.x + camera.w / 2.0 - BUTTON_WIDTH / 2.0,
-            camera.y + camera.h / 1.75 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 2.0, String::from("Retour au jeu"))?;
+        let pos = ui.place(Anchor::Center, (BUTTON_WIDTH, BUTTON_HEIGHT), (0.0, ui.area().h * BACK_OFFSET));
+        let mut back_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.x, pos.y,
+            color_not_hover, color_hover, 6.0, String::from("Retour au jeu"))?;
         back_button.set_text_scale(128.0);
 
-        let mut quit_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, camera.x + camera.w / 2.0 - BUTTON_WIDTH / 2.0,
-            camera.y + camera.h / 1.3 - BUTTON_HEIGHT / 2.0, color_not_hover, color_hover, 2.0, String::from("Abandonner"))?;
+        let pos = ui.place(Anchor::Center, (BUTTON_WIDTH, BUTTON_HEIGHT), (0.0, ui.area().h * GIVE_UP_OFFSET));
+        let mut quit_button = Button::new(ctx, BUTTON_WIDTH, BUTTON_HEIGHT, pos.x, pos.y,
+            color_not_hover, color_hover, 6.0, String::from("Abandonner"))?;
         quit_button.set_text_scale(128.0);
 
         let mut buttons = HashMap::new();
@@ -49,18 +57,17 @@ impl Pause {
         Ok(pause)
     }
 
-    pub fn draw(&self, ctx: &mut Context, camera: Rect) -> GameResult {
+    // On the UI canvas, between Ui::begin and Ui::end
+    pub fn draw(&self, ctx: &mut Context, ui: &Ui) -> GameResult {
+        let size = (self.pause_image.width() as f32 * IMAGE_SCALE, self.pause_image.height() as f32 * IMAGE_SCALE);
         let param = graphics::DrawParam::new()
-            .dest(Point2::new(
-                camera.x + camera.w / 2.0 - (self.pause_image.width() as f32 * 1.5)  / 2.0, 
-                camera.y + camera.h / 3.5 - (self.pause_image.height() as f32 * 1.5) / 2.0
-            )).scale(Vector2::new(1.5, 1.5));
+            .dest(ui.place(Anchor::Center, size, (0.0, ui.area().h * IMAGE_OFFSET)))
+            .scale(Vector2::new(IMAGE_SCALE, IMAGE_SCALE));
         graphics::draw(ctx, &self.pause_image, param)?;
 
-        // Drawing the buttons and texts "manually" because the screen is zoomed in so it's a pain
-        // (it ended up not being too much of a pain, but it was a pain to end up in this
-        // non-painful way).
-        const SCALE: f32 = 0.1;
+        // Drawing the texts "manually", a big font scaled down stays sharp once
+        // the canvas is stretched to the window
+        const SCALE: f32 = 0.3;
         for (_, button) in self.buttons.iter() {
             graphics::draw(ctx, button.get_border(), (button.get_pos(),))?;
 
@@ -73,23 +80,17 @@ impl Pause {
         Ok(())
     }
 
-    pub fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, camera: Rect, screen_size: (f32, f32)) {
-        // Have to apply the screen / zoom ratio the the mouse pos because they're
-        // the window mouse pos
-        let (x_ratio, y_ratio) = (screen_size.0 / camera.w, screen_size.1 / camera.h);
+    // The mouse pos is where it is on the UI canvas, see Ui::screen_to_ui
+    pub fn mouse_motion_event(&mut self, ctx: &mut Context, pos: Point2<f32>) {
         for (_, button) in self.buttons.iter_mut() {
-            button.mouse_motion_event(ctx, camera.x + x / x_ratio, camera.y + y / y_ratio);
+            button.mouse_motion_event(ctx, pos.x, pos.y);
         }
     }
 
-    pub fn mouse_button_down_event(&self, mouse_button: MouseButton, x: f32, y: f32,
-        camera: Rect, screen_size: (f32, f32)) -> u8
-    {
-        // Same deal as above 
+    pub fn mouse_button_down_event(&self, mouse_button: MouseButton, pos: Point2<f32>) -> u8 {
         if let MouseButton::Left = mouse_button {
-            let (x_ratio, y_ratio) = (screen_size.0 / camera.w, screen_size.1 / camera.h);
             for (which, button) in self.buttons.iter() {
-                if button.contains(camera.x + x / x_ratio, camera.y + y / y_ratio) {
+                if button.contains(pos.x, pos.y) {
                     match which {
                         ButtonType::BackToGame => return 1,
                         ButtonType::GiveUp => return 2
@@ -100,13 +101,13 @@ impl Pause {
         0
     }
 
-    pub fn resize_event(&mut self, ctx: &mut Context, camera: Rect) {
+    pub fn resize_event(&mut self, ctx: &mut Context, ui: &Ui) {
+        let pos = ui.place(Anchor::Center, (BUTTON_WIDTH, BUTTON_HEIGHT), (0.0, ui.area().h * BACK_OFFSET));
         let back_button = self.buttons.get_mut(&ButtonType::BackToGame).unwrap(); 
-        back_button.set_pos(ctx, camera.x + camera.w / 2.0 - BUTTON_WIDTH / 2.0,
-            camera.y + camera.h / 1.75 - BUTTON_HEIGHT / 2.0);
+        back_button.set_pos(ctx, pos.x, pos.y);
 
+        let pos = ui.place(Anchor::Center, (BUTTON_WIDTH, BUTTON_HEIGHT), (0.0, ui.area().h * GIVE_UP_OFFSET));
         let giveup_button = self.buttons.get_mut(&ButtonType::GiveUp).unwrap();
-        giveup_button.set_pos(ctx, camera.x + camera.w / 2.0 - BUTTON_WIDTH / 2.0,
-            camera.y + camera.h / 1.3 - BUTTON_HEIGHT / 2.0);
+        giveup_button.set_pos(ctx, pos.x, pos.y);
     }
 }
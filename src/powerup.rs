@@ -1,56 +1,290 @@
 use ggez::{
-    Context, graphics, GameResult,
+    Context, graphics, GameResult, GameError,
     graphics::Rect,
     nalgebra::{ Vector2, Point2 }
 };
 use rand::Rng;
+use serde::Deserialize;
 use crate::particle::Particle;
+use crate::utils::game_rng;
+use crate::map::Map;
+use crate::bertrand::BertrandKind;
+use crate::loot::LootTable;
+use std::collections::HashMap;
 
-// Powerups
+// Powerups, with their values once rolled
 #[derive(Clone)]
 pub enum Powerups {
+    // Time left, number of Bertrands a bullet goes through
     PiercingBullet((f32, u8)),
-    SpeedBoost((f32, f32)),
+    // Time left, speed multiplier, max speed
+    SpeedBoost((f32, f32, f32)),
     Heal(u8),
-    AmmoRestock(u32)
+    AmmoRestock(u32),
+    Coin(u32),
+    // Number of hits it can still take for Fabien
+    Shield(u8),
+    // Time left, Bertrands don't move at all meanwhile
+    TimeFreeze(f32),
+    // Time left, number of bullets per shot, angle of the spread (in radians)
+    Multishot((f32, u8, f32)),
+    // Time left, radius and speed at which pickups are pulled
+    Magnet((f32, f32, f32)),
+    // Time left, radius of the explosion
+    ExplosiveRounds((f32, f32))
 }
 
+impl Powerups {
+    // The time left of the powerups that only last a while
+    pub fn time_left(&mut self) -> Option<&mut f32> {
+        match self {
+            Powerups::PiercingBullet((time, _)) | Powerups::SpeedBoost((time, _, _))
+            | Powerups::TimeFreeze(time) | Powerups::Multishot((time, _, _))
+            | Powerups::Magnet((time, _, _)) | Powerups::ExplosiveRounds((time, _)) => Some(time),
+            _ => None
+        }
+    }
+
+    // The timed ones are over when their time is up, the shield when it can't take any more hit
+    pub fn is_over(&self) -> bool {
+        match *self {
+            Powerups::Shield(hits) => hits == 0,
+            Powerups::PiercingBullet((time, _)) | Powerups::SpeedBoost((time, _, _))
+            | Powerups::TimeFreeze(time) | Powerups::Multishot((time, _, _))
+            | Powerups::Magnet((time, _, _)) | Powerups::ExplosiveRounds((time, _)) => time <= 0.0,
+            _ => true
+        }
+    }
+
+    pub fn same_kind(&self, other: &Powerups) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+// What a powerup does, as written in powerups.json. The values are
+// [min, max] ranges, rolled each time one of them spawns.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum EffectDef {
+    PiercingBullet { duration: (f32, f32), pierce: (u8, u8) },
+    SpeedBoost { duration: (f32, f32), multiplier: (f32, f32), max_speed: f32 },
+    Heal { amount: (u8, u8) },
+    AmmoRestock { amount: (u32, u32) },
+    Coin { value: (u32, u32) },
+    Shield { hits: (u8, u8) },
+    TimeFreeze { duration: (f32, f32) },
+    // The spread is in degrees
+    Multishot { duration: (f32, f32), bullets: u8, spread: f32 },
+    Magnet { duration: (f32, f32), radius: f32, strength: f32 },
+    ExplosiveRounds { duration: (f32, f32), radius: f32 }
+}
+
+// A [min, max] range that can be rolled: gen_range panics on an empty one
+fn check_range<T: PartialOrd + std::fmt::Debug>(name: &str, range: (T, T)) -> Result<(), String> {
+    // Written that way so that NaN doesn't get through either
+    if range.0 <= range.1 { Ok(()) } else { Err(format!("{} : [{:?}, {:?}] is an empty range", name, range.0, range.1)) }
+}
+
+impl EffectDef {
+    // Every range has to be rollable, and every duration positive
+    fn validate(&self) -> Result<(), String> {
+        let duration = match *self {
+            EffectDef::PiercingBullet { duration, pierce } => { check_range("pierce", pierce)?; Some(duration) },
+            EffectDef::SpeedBoost { duration, multiplier, .. } => { check_range("multiplier", multiplier)?; Some(duration) },
+            EffectDef::Heal { amount } => { check_range("amount", amount)?; None },
+            EffectDef::AmmoRestock { amount } => { check_range("amount", amount)?; None },
+            EffectDef::Coin { value } => { check_range("value", value)?; None },
+            EffectDef::Shield { hits } => { check_range("hits", hits)?; None },
+            EffectDef::TimeFreeze { duration } | EffectDef::Multishot { duration, .. }
+            | EffectDef::Magnet { duration, .. } | EffectDef::ExplosiveRounds { duration, .. } => Some(duration)
+        };
+        if let Some(duration) = duration {
+            check_range("duration", duration)?;
+            if duration.0 <= 0.0 { return Err(String::from("duration : has to be positive")); }
+        }
+
+        Ok(())
+    }
+
+    pub fn roll(&self) -> Powerups {
+        game_rng(|rng| match *self {
+            EffectDef::PiercingBullet { duration, pierce } => Powerups::PiercingBullet((
+                rng.gen_range(duration.0..=duration.1), rng.gen_range(pierce.0..=pierce.1))),
+            EffectDef::SpeedBoost { duration, multiplier, max_speed } => Powerups::SpeedBoost((
+                rng.gen_range(duration.0..=duration.1), rng.gen_range(multiplier.0..=multiplier.1), max_speed)),
+            EffectDef::Heal { amount } => Powerups::Heal(rng.gen_range(amount.0..=amount.1)),
+            EffectDef::AmmoRestock { amount } => Powerups::AmmoRestock(rng.gen_range(amount.0..=amount.1)),
+            EffectDef::Coin { value } => Powerups::Coin(rng.gen_range(value.0..=value.1)),
+            EffectDef::Shield { hits } => Powerups::Shield(rng.gen_range(hits.0..=hits.1)),
+            EffectDef::TimeFreeze { duration } => Powerups::TimeFreeze(rng.gen_range(duration.0..=duration.1)),
+            EffectDef::Multishot { duration, bullets, spread } => Powerups::Multishot((
+                rng.gen_range(duration.0..=duration.1), bullets, spread.to_radians())),
+            EffectDef::Magnet { duration, radius, strength } => Powerups::Magnet((
+                rng.gen_range(duration.0..=duration.1), radius, strength)),
+            EffectDef::ExplosiveRounds { duration, radius } => Powerups::ExplosiveRounds((
+                rng.gen_range(duration.0..=duration.1), radius))
+        })
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PowerupDef {
+    pub id: String,
+    pub sprite: String,
+    pub scale: f32,
+    // How long it stays on the ground before disappearing, in seconds
+    #[serde(default = "default_lifetime")]
+    pub lifetime: f32,
+    // How likely it is to spawn on its own, for each wave (the last one is
+    // used for the waves after that). Nothing means it never does, like the
+    // ones that only drop from Bertrands.
+    #[serde(default)]
+    pub spawn_weights: Vec<u32>,
+    pub effect: EffectDef,
+    // The sprite, loaded once with the catalogue and shared by all the powerups of this kind
+    #[serde(skip)]
+    image: Option<graphics::Image>
+}
+
+fn default_lifetime() -> f32 { 30.0 }
+
+impl PowerupDef {
+    pub fn spawn_weight(&self, wave: u32) -> u32 {
+        let index = ((wave.max(1) - 1) as usize).min(self.spawn_weights.len().saturating_sub(1));
+        self.spawn_weights.get(index).copied().unwrap_or(0)
+    }
+}
+
+// Every powerup of the game, loaded from powerups.json
+#[derive(Deserialize, Clone, Debug)]
+pub struct PowerupCatalogue {
+    powerups: Vec<PowerupDef>,
+    // What the Bertrands drop when they die, nothing for the kinds that aren't in there
+    #[serde(default)]
+    loot: HashMap<BertrandKind, LootTable>
+}
+
+impl PowerupCatalogue {
+    pub fn load(ctx: &mut Context, path: &str) -> GameResult<PowerupCatalogue> {
+        let file = ggez::filesystem::open(ctx, path)?;
+        let mut catalogue: PowerupCatalogue = serde_json::from_reader(file)
+            .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))?;
+        catalogue.validate()
+            .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))?;
+
+        // Some of them have the same sprite, it's only loaded once
+        let mut images: HashMap<String, graphics::Image> = HashMap::new();
+        for def in catalogue.powerups.iter_mut() {
+            if !images.contains_key(&def.sprite) {
+                images.insert(def.sprite.clone(), graphics::Image::new(ctx, &def.sprite)?);
+            }
+            def.image = Some(images[&def.sprite].clone());
+        }
+
+        Ok(catalogue)
+    }
+
+    // What serde can't check: the ranges have to be rollable, the weights
+    // of a wave can't add up past what a u32 holds, and the loot has to make sense
+    fn validate(&self) -> Result<(), String> {
+        for def in self.powerups.iter() {
+            def.effect.validate().map_err(|e| format!("powerup {} : {}", def.id, e))?;
+            let positive = |value: f32| value > 0.0 && value.is_finite();
+            if !positive(def.scale) || !positive(def.lifetime) {
+                return Err(format!("powerup {} : scale and lifetime have to be positive", def.id));
+            }
+        }
+
+        let nb_waves = self.powerups.iter().map(|def| def.spawn_weights.len()).max().unwrap_or(0);
+        for wave in 1..=nb_waves as u32 {
+            let mut total: u32 = 0;
+            for def in self.powerups.iter() {
+                total = total.checked_add(def.spawn_weight(wave))
+                    .ok_or_else(|| format!("powerup {} : spawn_weights add up to too much (wave {})", def.id, wave))?;
+            }
+        }
+
+        for (kind, table) in self.loot.iter() {
+            table.validate(self).map_err(|e| format!("loot {:?} : {}", kind, e))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PowerupDef> {
+        self.powerups.iter().find(|def| def.id == id)
+    }
+
+    pub fn loot(&self, kind: BertrandKind) -> Option<&LootTable> {
+        self.loot.get(&kind)
+    }
+
+    // Pick one of the powerups that can spawn during this wave, according to
+    // their weights, among the ones accepted by the filter
+    pub fn pick(&self, wave: u32, filter: impl Fn(&PowerupDef) -> bool) -> Option<&PowerupDef> {
+        let candidates: Vec<(&PowerupDef, u32)> = self.powerups.iter()
+            .filter(|def| filter(def))
+            .map(|def| (def, def.spawn_weight(wave)))
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
+        let total: u32 = candidates.iter().map(|(_, weight)| weight).sum();
+        if total == 0 { return None; }
+
+        let mut roll = game_rng(|rng| rng.gen_range(0..total));
+        for (def, weight) in candidates {
+            if roll < weight { return Some(def); }
+            roll -= weight;
+        }
+
+        unreachable!()
+    }
+}
+
+// Blinks for that long before disappearing
+const WARNING_TIME: f32 = 5.0;
+
 pub struct Powerup {
     powerup: Powerups,
     sprite: graphics::Image,
     hitbox: Rect,
     scale: f32,
+    age: f32,
+    lifetime: f32,
+    // Only moves the sprite up and down, the hitbox stays where it is
+    bob_offset: f32,
     particles: Vec<Particle>,
     time_since_last_particle: f64
 }
 
 impl Powerup {
-    pub fn new(ctx: &mut Context, map_size: (f32, f32)) -> GameResult<Powerup> {
-        let mut rng = rand::thread_rng();
-
-        let new_powerup = match rng.gen_range(0..100) {
-            0..=19 => Powerups::PiercingBullet((rng.gen_range(10..=20) as f32, rng.gen_range(1..=3))),
-            20..=49 => Powerups::SpeedBoost((rng.gen_range(15..=20) as f32, rng.gen_range(14..17) as f32 / 10.0)),
-            50..=69 => Powerups::Heal(rng.gen_range(1..=3)),
-            _ => Powerups::AmmoRestock(rng.gen_range(15..=20))
-        };
+    // Put the powerup somewhere random on the map that Fabien (at from) can get
+    // to, or nowhere if no such spot was found
+    pub fn new(ctx: &mut Context, def: &PowerupDef, map: &Map, from: Point2<f32>) -> GameResult<Option<Powerup>> {
+        let mut powerup = Powerup::from_def(ctx, def, Point2::new(0.0, 0.0))?;
+        match map.random_powerup_spot((powerup.hitbox.w, powerup.hitbox.h), from) {
+            Some(pos) => {
+                powerup.hitbox.x = pos.x;
+                powerup.hitbox.y = pos.y;
+                Ok(Some(powerup))
+            },
+            None => Ok(None)
+        }
+    }
 
-        let sprite_path = match new_powerup {
-            Powerups::PiercingBullet(_) => "/piercing_bullet.png",
-            Powerups::SpeedBoost(_) => "/speed_powerup.png",
-            Powerups::Heal(_) => "/sandwich.png",
-            Powerups::AmmoRestock(_) => "/bullet.png"
-        };
-        
-        let sprite = graphics::Image::new(ctx, sprite_path)?;
-        let scale = match new_powerup {
-            Powerups::Heal(_) => 0.04,
-            _ => 0.6
+    // Create a given powerup at a given position (its top left corner),
+    // used for the powerups dropped by Bertrands
+    pub fn from_def(ctx: &mut Context, def: &PowerupDef, pos: Point2<f32>) -> GameResult<Powerup> {
+        let new_powerup = def.effect.roll();
+        let sprite = match &def.image {
+            Some(image) => image.clone(),
+            // Only a def that isn't from PowerupCatalogue::load
+            None => graphics::Image::new(ctx, &def.sprite)?
         };
+        let scale = def.scale;
 
         let hitbox = Rect::new(
-            rng.gen_range(0..map_size.0 as u32) as f32,
-            rng.gen_range(0..map_size.1 as u32) as f32,
+            pos.x,
+            pos.y,
             sprite.width() as f32 * scale,
             sprite.height() as f32 * scale
          );
@@ -60,6 +294,9 @@ impl Powerup {
             sprite: sprite,
             hitbox: hitbox,
             scale: scale,
+            age: 0.0,
+            lifetime: def.lifetime,
+            bob_offset: 0.0,
             particles: vec![],
             time_since_last_particle: 0.0
         };
@@ -69,8 +306,9 @@ impl Powerup {
 
     pub fn update(&mut self, ctx: &mut Context, time_passed: f64, fps: f64) -> GameResult {
         const SPEED: f32 = 3.0;
-        const HEIGHT: f32 = 0.1;
-        self.hitbox.y += (time_passed as f32 * SPEED).sin() * HEIGHT;
+        const HEIGHT: f32 = 2.0;
+        self.bob_offset = (time_passed as f32 * SPEED).sin() * HEIGHT;
+        self.age += 1.0 / fps as f32;
 
         self.time_since_last_particle += 1.0 / fps;
 
@@ -105,20 +343,45 @@ impl Powerup {
     }
     
     pub fn draw(&self, ctx: &mut Context) -> GameResult {
-        let param = graphics::DrawParam::default()
-            .dest(Point2::new(self.hitbox.x, self.hitbox.y))
-            .scale(Vector2::new(self.scale, self.scale));
+        // Blinks faster and faster when it's about to disappear
+        let time_left = self.lifetime - self.age;
+        let visible = if time_left < WARNING_TIME {
+            let blink_speed = 8.0 + 16.0 * (1.0 - time_left / WARNING_TIME);
+            (self.age * blink_speed).sin() > 0.0
+        } else { true };
+
+        if visible {
+            let param = graphics::DrawParam::default()
+                .dest(Point2::new(self.hitbox.x, self.hitbox.y + self.bob_offset))
+                .scale(Vector2::new(self.scale, self.scale));
 
-        graphics::draw(ctx, &self.sprite, param)?;
+            graphics::draw(ctx, &self.sprite, param)?;
+        }
         for p in self.particles.iter() { p.draw(ctx)?; }
 
         Ok(())
     }
 
+    // Move it towards the target if it's close enough, for the magnet
+    pub fn pull_towards(&mut self, target: Point2<f32>, radius: f32, distance: f32) {
+        let center = Point2::new(self.hitbox.x + self.hitbox.w / 2.0, self.hitbox.y + self.hitbox.h / 2.0);
+        let to_target = target - center;
+        let dist = to_target.norm();
+        if dist > radius || dist < 1.0 { return; }
+
+        let step = to_target * (distance.min(dist) / dist);
+        self.hitbox.x += step.x;
+        self.hitbox.y += step.y;
+    }
+
     pub fn get_hitbox(&self) -> Rect {
         self.hitbox
     }
 
+    pub fn is_expired(&self) -> bool {
+        self.age >= self.lifetime
+    }
+
     pub fn get_powerup(&self) -> Powerups {
         self.powerup.clone()
     }
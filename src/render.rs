@@ -0,0 +1,42 @@
+use ggez::graphics::Rect;
+
+use crate::map::Map;
+
+// Something standing on the map, to be drawn in the right order
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Drawable {
+    // Indices in their own lists
+    Obstacle(usize),
+    Powerup(usize),
+    Bertrand(usize),
+    Fabien
+}
+
+// Where something's feet are, the further down the later it's drawn. In a
+// bush it's the middle instead, so that the leaves end up in front of it.
+pub fn depth(map: &Map, hitbox: Rect) -> f32 {
+    if map.hides(hitbox) { hitbox.y + hitbox.h / 2.0 } else { hitbox.y + hitbox.h }
+}
+
+// Everything that has to be drawn this frame, sorted from the back to the front
+pub struct RenderQueue {
+    items: Vec<(f32, Drawable)>
+}
+
+impl RenderQueue {
+    pub fn new(capacity: usize) -> RenderQueue {
+        RenderQueue {
+            items: Vec::with_capacity(capacity)
+        }
+    }
+
+    pub fn push(&mut self, depth: f32, drawable: Drawable) {
+        self.items.push((depth, drawable));
+    }
+
+    // Stable, so that things at the same depth keep the order they were pushed in
+    pub fn sorted(mut self) -> Vec<Drawable> {
+        self.items.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        self.items.into_iter().map(|(_, drawable)| drawable).collect()
+    }
+}
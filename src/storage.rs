@@ -0,0 +1,39 @@
+use ggez::{ Context, GameResult, GameError };
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+
+// Lives in the user data folder of ggez, not with the resources
+const SAVE_PATH: &str = "/save.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+pub struct DailyRecord {
+    // The score of the one attempt that counted that day
+    pub scored: Option<u32>,
+    pub best_score: u32,
+    pub best_wave: u32,
+    pub attempts: u32
+}
+
+// What's kept on this computer between two launches of the game
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Storage {
+    // Keyed by the date of the challenge, like "2021-03-14"
+    #[serde(default)]
+    pub daily: HashMap<String, DailyRecord>
+}
+
+impl Storage {
+    // No save yet (or a broken one) just means starting from scratch
+    pub fn load(ctx: &mut Context) -> Storage {
+        match ggez::filesystem::open(ctx, SAVE_PATH) {
+            Ok(file) => serde_json::from_reader(file).unwrap_or_default(),
+            Err(_) => Storage::default()
+        }
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> GameResult {
+        let file = ggez::filesystem::create(ctx, SAVE_PATH)?;
+        serde_json::to_writer_pretty(file, self)
+            .map_err(|e| GameError::FilesystemError(format!("{} : {}", SAVE_PATH, e)))
+    }
+}
@@ -0,0 +1,101 @@
+// What can be found on the map besides grass. Each kind says who it stops,
+// what it does to the ones going through it, and which part of its sprite
+// counts (the trunk of a tree, but the whole puddle of mud).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ObstacleKind {
+    // Blocks everyone and stops bullets, and Bertrands can't see through the leaves
+    Tree,
+    // Same as a tree, but lower
+    Rock,
+    // Nobody can walk in it, but bullets fly over it
+    Water,
+    // Slows down whoever goes through it, and Fabien can hide in it
+    Bush,
+    // Slows down everyone
+    Mud
+}
+
+impl ObstacleKind {
+    // How it's called in the Tiled maps (the type of the object)
+    pub fn from_name(name: &str) -> Option<ObstacleKind> {
+        match name {
+            "tree" => Some(ObstacleKind::Tree),
+            "rock" => Some(ObstacleKind::Rock),
+            "water" => Some(ObstacleKind::Water),
+            "bush" => Some(ObstacleKind::Bush),
+            "mud" => Some(ObstacleKind::Mud),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObstacleKind::Tree => "tree",
+            ObstacleKind::Rock => "rock",
+            ObstacleKind::Water => "water",
+            ObstacleKind::Bush => "bush",
+            ObstacleKind::Mud => "mud"
+        }
+    }
+
+    pub fn blocks_walking(&self) -> bool {
+        matches!(self, ObstacleKind::Tree | ObstacleKind::Rock | ObstacleKind::Water)
+    }
+
+    pub fn stops_bullets(&self) -> bool {
+        matches!(self, ObstacleKind::Tree | ObstacleKind::Rock)
+    }
+
+    // Only the sprite of those is checked, a rock is too low to hide anything
+    pub fn blocks_sight(&self) -> bool {
+        matches!(self, ObstacleKind::Tree)
+    }
+
+    // Fabien can't be seen from afar when he's in it
+    pub fn hides(&self) -> bool {
+        matches!(self, ObstacleKind::Bush)
+    }
+
+    // Multiplies the speed of whoever walks in it
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            ObstacleKind::Bush => 0.6,
+            ObstacleKind::Mud => 0.45,
+            _ => 1.0
+        }
+    }
+
+    // Which part of the sprite, from the bottom, is solid (or slows down)
+    pub fn solid_part(&self) -> f32 {
+        match self {
+            ObstacleKind::Tree => 2.0 / 5.0,
+            ObstacleKind::Rock => 3.0 / 5.0,
+            _ => 1.0
+        }
+    }
+
+    // How many bullets it takes before it breaks, if bullets do anything to it
+    pub fn durability(&self) -> Option<u8> {
+        match self {
+            ObstacleKind::Tree => Some(6),
+            ObstacleKind::Rock => Some(15),
+            _ => None
+        }
+    }
+
+    // What flies off of it when it's shot
+    pub fn debris_color(&self) -> (u8, u8, u8) {
+        match self {
+            ObstacleKind::Tree => (115, 80, 45),
+            ObstacleKind::Rock => (135, 135, 140),
+            ObstacleKind::Bush => (56, 128, 48),
+            ObstacleKind::Water => (62, 124, 204),
+            ObstacleKind::Mud => (110, 80, 50)
+        }
+    }
+
+    // Flat on the ground, drawn under everything else
+    pub fn is_flat(&self) -> bool {
+        matches!(self, ObstacleKind::Water | ObstacleKind::Mud)
+    }
+}
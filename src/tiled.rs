@@ -0,0 +1,593 @@
+use ggez::{ Context, GameResult, GameError, nalgebra::Point2 };
+use serde::{ Deserialize, Serialize };
+use std::io::Read;
+use flate2::read::{ GzDecoder, ZlibDecoder };
+use xml::reader::{ EventReader, XmlEvent };
+
+// The gids keep the flipping of the tile in their highest bits
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+// A map made with Tiled (https://www.mapeditor.org), saved as .tmx or JSON. Only what
+// B-Hunt uses is read: the tile layers are the ground (grass), the objects with
+// a tile are obstacles (their type says which kind, "tree" when there's none) and
+// the point objects of type "spawn" say where things appear ("fabien", "bertrand"
+// or "powerup" in their name). The images of the tilesets have to be in the
+// resources folder.
+//
+// The tile layers can be plain lists of gids, CSV or base64 (uncompressed, zlib or
+// gzip), and the tilesets can be in the map or in their own file (.tsx, or JSON).
+// What isn't supported: infinite maps, zstd compression,
+// tilesets made of a single image (only "collection of images" ones work), flipped
+// tiles (they're drawn as they are in the tileset), and the objects in groups.
+#[derive(Deserialize, Serialize)]
+pub struct TiledMap {
+    // In tiles
+    pub width: u32,
+    pub height: u32,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    tilesets: Vec<Tileset>,
+    layers: Vec<Layer>
+}
+
+#[derive(Deserialize, Serialize)]
+struct Tileset {
+    // Not in the file of an external tileset, it's in the map
+    #[serde(default)]
+    firstgid: u32,
+    #[serde(default)]
+    name: String,
+    // Only "collection of images" tilesets, one image per tile
+    #[serde(default)]
+    tiles: Vec<TilesetTile>,
+    // The file of an external tileset (relative to the map), it's read in
+    // when the map is loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>
+}
+
+#[derive(Deserialize, Serialize)]
+struct TilesetTile {
+    id: u32,
+    image: String
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum Layer {
+    #[serde(rename = "tilelayer")]
+    Tiles {
+        #[serde(default)]
+        name: String,
+        width: u32,
+        #[serde(default)]
+        height: u32,
+        data: LayerData,
+        // "base64" when the data is a string, the compression is "" when there's none
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encoding: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<String>
+    },
+    #[serde(rename = "objectgroup")]
+    Objects {
+        #[serde(default)]
+        name: String,
+        objects: Vec<Object>
+    },
+    // Image layers, groups...
+    #[serde(other)]
+    Other
+}
+
+// The gids are decoded when the map is loaded, only the list is left after that
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum LayerData {
+    Gids(Vec<u32>),
+    Encoded(String)
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Object {
+    #[serde(default)]
+    pub name: String,
+    // Called "class" in some versions of Tiled
+    #[serde(default, rename = "type", alias = "class")]
+    pub kind: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    point: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    gid: u32
+}
+
+fn is_zero(gid: &u32) -> bool {
+    *gid == 0
+}
+
+// What a spawn point is for, it's the name of the point in Tiled
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SpawnKind {
+    Fabien,
+    Bertrand,
+    Powerup
+}
+
+impl SpawnKind {
+    pub fn from_name(name: &str) -> Option<SpawnKind> {
+        match name {
+            "fabien" => Some(SpawnKind::Fabien),
+            "bertrand" => Some(SpawnKind::Bertrand),
+            "powerup" => Some(SpawnKind::Powerup),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpawnKind::Fabien => "fabien",
+            SpawnKind::Bertrand => "bertrand",
+            SpawnKind::Powerup => "powerup"
+        }
+    }
+}
+
+impl TiledMap {
+    // An empty map (size in tiles), with a tileset for each list of images
+    pub fn new(size: (u32, u32), tile_size: u32, tilesets: &[(&str, &[&str])]) -> TiledMap {
+        let mut firstgid = 1;
+        let tilesets = tilesets.iter().map(|(name, images)| {
+            let tileset = Tileset {
+                firstgid: firstgid,
+                name: name.to_string(),
+                tiles: images.iter().enumerate().map(|(id, image)| TilesetTile {
+                    id: id as u32,
+                    // Relative to the map, which is in the maps folder
+                    image: format!("..{}", image)
+                }).collect(),
+                source: None
+            };
+            firstgid += images.len() as u32;
+            tileset
+        }).collect();
+
+        TiledMap {
+            width: size.0,
+            height: size.1,
+            tilewidth: tile_size,
+            tileheight: tile_size,
+            tilesets: tilesets,
+            layers: vec![]
+        }
+    }
+
+    pub fn load(ctx: &mut Context, path: &str) -> GameResult<TiledMap> {
+        let file = ggez::filesystem::open(ctx, path)?;
+        let mut map = if path.ends_with(".tmx") {
+            TiledMap::from_tmx(file, path)?
+        } else {
+            TiledMap::from_json(file, path)?
+        };
+
+        for tileset in map.tilesets.iter_mut() {
+            if let Some(source) = tileset.source.take() {
+                let firstgid = tileset.firstgid;
+                *tileset = load_tileset(ctx, &relative_path(path, &source))?;
+                tileset.firstgid = firstgid;
+            }
+        }
+
+        Ok(map)
+    }
+
+    // Everything but the external tilesets, which need the other files. The
+    // path is only for the errors.
+    pub fn from_json<R: Read>(json: R, path: &str) -> GameResult<TiledMap> {
+        let mut map: TiledMap = serde_json::from_reader(json)
+            .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))?;
+        map.decode_layers(path)?;
+
+        Ok(map)
+    }
+
+    // The same, from the XML Tiled saves by default
+    pub fn from_tmx<R: Read>(xml: R, path: &str) -> GameResult<TiledMap> {
+        let mut map = TiledMap { width: 0, height: 0, tilewidth: 0, tileheight: 0, tilesets: vec![], layers: vec![] };
+        // The tiles of a tileset have images (and maybe collision shapes, which
+        // aren't objects of the map), the ones of a layer's data have gids
+        let mut in_tileset = false;
+        let mut in_data = false;
+        let mut tile_id = None;
+        for event in EventReader::new(xml) {
+            match event.map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    let attribute = |key: &str| attributes.iter()
+                        .find(|attribute| attribute.name.local_name == key)
+                        .map(|attribute| attribute.value.clone());
+                    let integer = |key: &str| attribute(key).and_then(|value| value.parse::<u32>().ok()).unwrap_or(0);
+                    let number = |key: &str| attribute(key).and_then(|value| value.parse::<f32>().ok()).unwrap_or(0.0);
+                    match name.local_name.as_str() {
+                        "map" => {
+                            map.width = integer("width");
+                            map.height = integer("height");
+                            map.tilewidth = integer("tilewidth");
+                            map.tileheight = integer("tileheight");
+                        },
+                        "tileset" => {
+                            in_tileset = true;
+                            map.tilesets.push(Tileset {
+                                firstgid: integer("firstgid"),
+                                name: attribute("name").unwrap_or_default(),
+                                tiles: vec![],
+                                source: attribute("source")
+                            });
+                        },
+                        "tile" if in_tileset => tile_id = attribute("id").and_then(|id| id.parse().ok()),
+                        "tile" if in_data => if let Some(Layer::Tiles { data: LayerData::Gids(gids), .. }) = map.layers.last_mut() {
+                            gids.push(integer("gid"));
+                        },
+                        "image" => if let (Some(id), Some(image), Some(tileset)) = (tile_id, attribute("source"), map.tilesets.last_mut()) {
+                            tileset.tiles.push(TilesetTile { id: id, image: image });
+                        },
+                        "layer" => map.layers.push(Layer::Tiles {
+                            name: attribute("name").unwrap_or_default(),
+                            width: integer("width"),
+                            height: integer("height"),
+                            data: LayerData::Gids(vec![]),
+                            encoding: None,
+                            compression: None
+                        }),
+                        "data" => if let Some(Layer::Tiles { encoding, compression, .. }) = map.layers.last_mut() {
+                            in_data = true;
+                            *encoding = attribute("encoding");
+                            *compression = attribute("compression");
+                        },
+                        "objectgroup" if !in_tileset => map.layers.push(Layer::Objects {
+                            name: attribute("name").unwrap_or_default(),
+                            objects: vec![]
+                        }),
+                        "object" if !in_tileset => if let Some(Layer::Objects { objects, .. }) = map.layers.last_mut() {
+                            objects.push(Object {
+                                name: attribute("name").unwrap_or_default(),
+                                kind: attribute("type").or_else(|| attribute("class")).unwrap_or_default(),
+                                x: number("x"),
+                                y: number("y"),
+                                width: number("width"),
+                                height: number("height"),
+                                point: false,
+                                gid: integer("gid")
+                            });
+                        },
+                        "point" if !in_tileset => if let Some(Layer::Objects { objects, .. }) = map.layers.last_mut() {
+                            if let Some(object) = objects.last_mut() { object.point = true; }
+                        },
+                        _ => {}
+                    }
+                },
+                XmlEvent::Characters(text) if in_data => if let Some(Layer::Tiles { data, encoding, .. }) = map.layers.last_mut() {
+                    *data = if encoding.as_deref() == Some("csv") {
+                        *encoding = None;
+                        LayerData::Gids(parse_csv(&text)
+                            .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))?)
+                    } else {
+                        // Decoded like the ones of the JSON maps, once everything is read
+                        LayerData::Encoded(text)
+                    };
+                },
+                XmlEvent::EndElement { name } => match name.local_name.as_str() {
+                    "tileset" => in_tileset = false,
+                    "tile" => tile_id = None,
+                    "data" => in_data = false,
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        map.decode_layers(path)?;
+
+        Ok(map)
+    }
+
+    // The base64 layers become lists of gids. The path is only for the errors.
+    fn decode_layers(&mut self, path: &str) -> GameResult {
+        for layer in self.layers.iter_mut() {
+            if let Layer::Tiles { data, encoding, compression, .. } = layer {
+                if let LayerData::Encoded(encoded) = data {
+                    if encoding.as_deref() != Some("base64") {
+                        return Err(GameError::ResourceLoadError(
+                            format!("{} : encodage de calque non supporté {:?}", path, encoding)));
+                    }
+                    let gids = decode_gids(encoded, compression.as_deref())
+                        .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))?;
+                    *data = LayerData::Gids(gids);
+                    *encoding = None;
+                    *compression = None;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Goes in the user folder of ggez, the resources folder isn't written to
+    pub fn save(&self, ctx: &mut Context, path: &str) -> GameResult {
+        if let Some((folder, _)) = path.rsplit_once('/') {
+            if !folder.is_empty() { ggez::filesystem::create_dir(ctx, folder)?; }
+        }
+        let file = ggez::filesystem::create(ctx, path)?;
+        serde_json::to_writer(file, self)
+            .map_err(|e| GameError::FilesystemError(format!("{} : {}", path, e)))
+    }
+
+    pub fn pixel_size(&self) -> (f32, f32) {
+        ((self.width * self.tilewidth) as f32, (self.height * self.tileheight) as f32)
+    }
+
+    // The gid of the first tile of that tileset
+    pub fn first_gid(&self, tileset: &str) -> Option<u32> {
+        self.tilesets.iter().find(|t| t.name == tileset).map(|t| t.firstgid)
+    }
+
+    // Path (for ggez) of the image of that tile, if it's in one of the tilesets
+    pub fn tile_image(&self, gid: u32) -> Option<String> {
+        let gid = gid & GID_MASK;
+        let tileset = self.tilesets.iter()
+            .filter(|tileset| tileset.firstgid <= gid)
+            .max_by_key(|tileset| tileset.firstgid)?;
+        let tile = tileset.tiles.iter().find(|tile| tile.id == gid - tileset.firstgid)?;
+
+        // Tiled saves it relative to the map, only the name of the file matters
+        let file_name = tile.image.rsplit(['/', '\\']).next()?;
+        Some(format!("/{}", file_name))
+    }
+
+    // Every tile of every tile layer, as (gid, top left corner)
+    pub fn tiles(&self) -> Vec<(u32, Point2<f32>)> {
+        let mut tiles = vec![];
+        for layer in self.layers.iter() {
+            if let Layer::Tiles { width, data: LayerData::Gids(data), .. } = layer {
+                for (i, gid) in data.iter().enumerate().filter(|(_, gid)| **gid != 0) {
+                    let (col, row) = (i as u32 % width, i as u32 / width);
+                    tiles.push((*gid, Point2::new((col * self.tilewidth) as f32, (row * self.tileheight) as f32)));
+                }
+            }
+        }
+
+        tiles
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &Object> {
+        self.layers.iter().flat_map(|layer| match layer {
+            Layer::Objects { objects, .. } => objects.iter(),
+            _ => [].iter()
+        })
+    }
+
+    // One gid per tile, row by row, 0 meaning no tile
+    pub fn add_tile_layer(&mut self, name: &str, data: Vec<u32>) {
+        self.layers.push(Layer::Tiles {
+            name: name.to_string(),
+            width: self.width,
+            height: self.height,
+            data: LayerData::Gids(data),
+            encoding: None,
+            compression: None
+        });
+    }
+
+    pub fn add_object_layer(&mut self, name: &str, objects: Vec<Object>) {
+        self.layers.push(Layer::Objects { name: name.to_string(), objects: objects });
+    }
+}
+
+impl Object {
+    // An obstacle of that kind (see ObstacleKind) showing the tile gid, with its top left corner at pos
+    pub fn tile(gid: u32, kind: &str, pos: Point2<f32>, size: (f32, f32)) -> Object {
+        Object {
+            name: String::new(),
+            kind: kind.to_string(),
+            x: pos.x,
+            y: pos.y + size.1,
+            width: size.0,
+            height: size.1,
+            point: false,
+            gid: gid
+        }
+    }
+
+    pub fn spawn(kind: SpawnKind, pos: Point2<f32>) -> Object {
+        Object {
+            name: kind.name().to_string(),
+            kind: "spawn".to_string(),
+            x: pos.x,
+            y: pos.y,
+            width: 0.0,
+            height: 0.0,
+            point: true,
+            gid: 0
+        }
+    }
+
+    // Objects showing a tile have its gid, the other ones are shapes and points
+    pub fn get_gid(&self) -> Option<u32> {
+        if self.gid == 0 { None } else { Some(self.gid) }
+    }
+
+    // Tile objects are anchored at their bottom left corner in Tiled
+    pub fn top_left(&self) -> Point2<f32> {
+        if self.gid != 0 {
+            Point2::new(self.x, self.y - self.height)
+        } else {
+            Point2::new(self.x, self.y)
+        }
+    }
+
+    pub fn get_spawn(&self) -> Option<SpawnKind> {
+        if self.kind == "spawn" && self.gid == 0 { SpawnKind::from_name(&self.name) } else { None }
+    }
+}
+
+// Four bytes per gid (little endian) once it's decoded and decompressed
+fn decode_gids(encoded: &str, compression: Option<&str>) -> Result<Vec<u32>, String> {
+    let bytes = base64::decode(encoded.trim()).map_err(|e| e.to_string())?;
+    let mut decompressed = vec![];
+    let bytes = match compression {
+        None | Some("") => bytes,
+        Some("zlib") => {
+            ZlibDecoder::new(&bytes[..]).read_to_end(&mut decompressed).map_err(|e| e.to_string())?;
+            decompressed
+        },
+        Some("gzip") => {
+            GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed).map_err(|e| e.to_string())?;
+            decompressed
+        },
+        Some(other) => return Err(format!("compression non supportée « {} »", other))
+    };
+    if bytes.len() % 4 != 0 { return Err("les données du calque ne sont pas une liste de gids".to_string()); }
+
+    Ok(bytes.chunks_exact(4).map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]])).collect())
+}
+
+// The gids of a .tmx layer saved as CSV, separated by commas (and line breaks)
+fn parse_csv(text: &str) -> Result<Vec<u32>, String> {
+    text.split(',')
+        .map(|gid| gid.trim().parse().map_err(|_| format!("gid invalide « {} »", gid.trim())))
+        .collect()
+}
+
+// Where a file the map points to is, for ggez ("/maps/arena.json" and
+// "../tilesets/trees.tsx" give "/tilesets/trees.tsx")
+fn relative_path(map_path: &str, source: &str) -> String {
+    let mut parts: Vec<&str> = map_path.split('/').filter(|part| !part.is_empty()).collect();
+    parts.pop();
+    for part in source.split(['/', '\\']) {
+        match part {
+            "" | "." => {},
+            ".." => { parts.pop(); },
+            _ => parts.push(part)
+        }
+    }
+
+    format!("/{}", parts.join("/"))
+}
+
+// An external tileset, as XML (.tsx) or JSON
+fn load_tileset(ctx: &mut Context, path: &str) -> GameResult<Tileset> {
+    let file = ggez::filesystem::open(ctx, path)?;
+    if !path.ends_with(".tsx") {
+        return serde_json::from_reader(file)
+            .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)));
+    }
+
+    let mut tileset = Tileset { firstgid: 0, name: String::new(), tiles: vec![], source: None };
+    let mut tile_id = None;
+    for event in EventReader::new(file) {
+        match event.map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let attribute = |key: &str| attributes.iter()
+                    .find(|attribute| attribute.name.local_name == key)
+                    .map(|attribute| attribute.value.clone());
+                match name.local_name.as_str() {
+                    "tileset" => tileset.name = attribute("name").unwrap_or_default(),
+                    "tile" => tile_id = attribute("id").and_then(|id| id.parse().ok()),
+                    // The image of the whole tileset is outside of any tile
+                    "image" => if let (Some(id), Some(image)) = (tile_id, attribute("source")) {
+                        tileset.tiles.push(TilesetTile { id: id, image: image });
+                    },
+                    _ => {}
+                }
+            },
+            XmlEvent::EndElement { name } if name.local_name == "tile" => tile_id = None,
+            _ => {}
+        }
+    }
+
+    if tileset.tiles.is_empty() {
+        return Err(GameError::ResourceLoadError(
+            format!("{} : seules les collections d'images sont supportées comme tilesets", path)));
+    }
+
+    Ok(tileset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use flate2::{ Compression, write::ZlibEncoder };
+
+    const GIDS: [u32; 4] = [1, 0, 7, 0x8000_0002];
+
+    fn layer_json(data: &str, compression: &str) -> String {
+        format!(r#"{{ "width": 2, "height": 2, "tilewidth": 16, "tileheight": 16, "tilesets": [],
+            "layers": [ {{ "type": "tilelayer", "width": 2, "height": 2, "data": "{}",
+                "encoding": "base64", "compression": "{}" }} ] }}"#, data, compression)
+    }
+
+    #[test]
+    fn base64_layers_are_decoded() {
+        let bytes: Vec<u8> = GIDS.iter().flat_map(|gid| gid.to_le_bytes()).collect();
+        let map = TiledMap::from_json(layer_json(&base64::encode(&bytes), "").as_bytes(), "test.json").unwrap();
+        let gids: Vec<u32> = map.tiles().iter().map(|(gid, _)| *gid).collect();
+        assert_eq!(gids, vec![1, 7, 0x8000_0002]);
+
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&bytes).unwrap();
+        let compressed = base64::encode(encoder.finish().unwrap());
+        let map = TiledMap::from_json(layer_json(&compressed, "zlib").as_bytes(), "test.json").unwrap();
+        assert_eq!(map.tiles().len(), 3);
+        assert_eq!(map.tiles()[1].1, Point2::new(0.0, 16.0));
+
+        assert!(TiledMap::from_json(layer_json(&compressed, "zstd").as_bytes(), "test.json").is_err());
+    }
+
+    #[test]
+    fn tmx_maps_are_read() {
+        let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" name="obstacles" tilewidth="32" tileheight="32" tilecount="1">
+  <tile id="0">
+   <image source="../tree.png" width="32" height="32"/>
+   <objectgroup draworder="index"><object id="1" x="4" y="4" width="24" height="24"/></objectgroup>
+  </tile>
+ </tileset>
+ <tileset firstgid="2" source="grass.tsx"/>
+ <layer id="1" name="herbe" width="2" height="2">
+  <data encoding="csv">
+2,0,
+0,2
+</data>
+ </layer>
+ <objectgroup id="2" name="objets">
+  <object id="1" type="bush" gid="1" x="0" y="32" width="32" height="32"/>
+  <object id="2" name="fabien" class="spawn" x="8" y="24"><point/></object>
+ </objectgroup>
+</map>"#;
+
+        let map = TiledMap::from_tmx(tmx.as_bytes(), "test.tmx").unwrap();
+        assert_eq!(map.pixel_size(), (32.0, 32.0));
+        assert_eq!(map.tile_image(1), Some("/tree.png".to_string()));
+        assert_eq!(map.tilesets[1].source.as_deref(), Some("grass.tsx"));
+        assert_eq!(map.tiles(), vec![(2, Point2::new(0.0, 0.0)), (2, Point2::new(16.0, 16.0))]);
+
+        // The collision shape of the tree isn't an object of the map
+        let objects: Vec<&Object> = map.objects().collect();
+        assert_eq!(objects.len(), 2);
+        assert_eq!((objects[0].kind.as_str(), objects[0].get_gid()), ("bush", Some(1)));
+        assert_eq!(objects[0].top_left(), Point2::new(0.0, 0.0));
+        assert_eq!(objects[1].get_spawn(), Some(SpawnKind::Fabien));
+    }
+
+    #[test]
+    fn external_tilesets_are_next_to_the_map() {
+        assert_eq!(relative_path("/maps/arena.json", "trees.tsx"), "/maps/trees.tsx");
+        assert_eq!(relative_path("/maps/arena.json", "../tilesets/trees.tsx"), "/tilesets/trees.tsx");
+        assert_eq!(relative_path("/maps/arena.json", "..\\trees.json"), "/trees.json");
+    }
+}
@@ -0,0 +1,140 @@
+use ggez::{
+    graphics, Context, GameResult,
+    graphics::Rect,
+    nalgebra::Point2
+};
+
+// The UI is laid out on a canvas that's always this tall (at the normal
+// scale), it's stretched to the window whatever its size and the zoom
+const REFERENCE_HEIGHT: f32 = 720.0;
+
+// Which corner (or side, or the middle) of the screen something sticks to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight
+}
+
+impl Anchor {
+    // How far along the screen it is, from 0 (left or top) to 1 (right or bottom)
+    fn factors(&self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum UiScale {
+    Small,
+    Normal,
+    Large
+}
+
+impl UiScale {
+    pub fn next(&self) -> UiScale {
+        match self {
+            UiScale::Small => UiScale::Normal,
+            UiScale::Normal => UiScale::Large,
+            UiScale::Large => UiScale::Small
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UiScale::Small => "Petite",
+            UiScale::Normal => "Normale",
+            UiScale::Large => "Grande"
+        }
+    }
+
+    fn factor(&self) -> f32 {
+        match self {
+            UiScale::Small => 0.8,
+            UiScale::Normal => 1.0,
+            UiScale::Large => 1.25
+        }
+    }
+}
+
+// Where the HUD, the menu, the pause menu and the game over screen are drawn,
+// on top of the world and in their own coordinates
+pub struct Ui {
+    screen_size: (f32, f32),
+    scale: UiScale
+}
+
+impl Ui {
+    pub fn new(screen_size: (f32, f32), scale: UiScale) -> Ui {
+        Ui {
+            screen_size: screen_size,
+            scale: scale
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: UiScale) {
+        self.scale = scale;
+    }
+
+    pub fn resize(&mut self, screen_size: (f32, f32)) {
+        self.screen_size = screen_size;
+    }
+
+    pub fn get_scale(&self) -> UiScale {
+        self.scale
+    }
+
+    pub fn get_screen_size(&self) -> (f32, f32) {
+        self.screen_size
+    }
+
+    // The whole canvas, it's as wide as it has to be to keep the window's shape
+    pub fn area(&self) -> Rect {
+        let height = REFERENCE_HEIGHT / self.scale.factor();
+        Rect::new(0.0, 0.0, height * self.screen_size.0 / self.screen_size.1, height)
+    }
+
+    // Everything drawn between these two is on the canvas
+    pub fn begin(&self, ctx: &mut Context) -> GameResult {
+        graphics::set_screen_coordinates(ctx, self.area())
+    }
+
+    // Back to the window's pixels, which is what the world and the editor expect
+    pub fn end(&self, ctx: &mut Context) -> GameResult {
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, self.screen_size.0, self.screen_size.1))
+    }
+
+    // Where the top left corner of something that big goes, for it to stick to
+    // the anchor. The margin keeps it away from the edges it sticks to, and
+    // moves it right or down when it's in the middle.
+    pub fn place(&self, anchor: Anchor, size: (f32, f32), margin: (f32, f32)) -> Point2<f32> {
+        let area = self.area();
+        let (fx, fy) = anchor.factors();
+        let inwards = |f: f32| if f > 0.5 { -1.0 } else { 1.0 };
+        Point2::new(
+            (area.w - size.0) * fx + margin.0 * inwards(fx),
+            (area.h - size.1) * fy + margin.1 * inwards(fy)
+        )
+    }
+
+    // Where a point of the window (the mouse) is on the canvas
+    pub fn screen_to_ui(&self, x: f32, y: f32) -> Point2<f32> {
+        let area = self.area();
+        Point2::new(x / self.screen_size.0 * area.w, y / self.screen_size.1 * area.h)
+    }
+}
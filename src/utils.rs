@@ -1,20 +1,30 @@
-use rand::Rng;
+use rand::{ Rng, SeedableRng, rngs::StdRng };
 use ggez::{
     graphics::{ Text, TextFragment, Font, Scale, Rect },
     graphics, Context,
     nalgebra::Point2
 };
-use std::{ env, fs };
+use std::{ env, fs, cell::{ Cell, RefCell } };
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct Stats {
     pub bertrand_killed: u64,
     pub shots: u64,
     pub powerups_activated: u64,
     pub hits_taken: u64,
-    pub time_played: u64
+    pub time_played: u64,
+    pub hits_absorbed: u64,
+    pub time_freezes: u64,
+    pub multishots: u64,
+    pub magnets: u64,
+    pub explosion_kills: u64
 }
 
+// The stats as they're named in the GameJolt data store
+pub const STATS_KEYS: [&str; 11] = ["bertrand_killed", "shots", "powerups_activated",
+    "hits_taken", "time_played", "games_played", "hits_absorbed", "time_freezes",
+    "multishots", "magnets", "explosion_kills"];
+
 #[derive(Clone)]
 pub struct Credentials {
     pub username: String,
@@ -31,12 +41,59 @@ pub enum Movement {
     Left,
 }
 
+thread_local! {
+    // Everything that shapes a game (the map, the spawns, the powerups...) is rolled
+    // from this one, so that the same seed gives the same map (the rest also depends
+    // on the frame rate and on what the player does). Things that are only
+    // there to look nice (particles and such) keep using rand::thread_rng.
+    static GAME_SEED: Cell<u64> = Cell::new(rand::thread_rng().gen());
+    static GAME_RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(GAME_SEED.with(|seed| seed.get())));
+}
+
+pub fn set_seed(seed: u64) {
+    GAME_SEED.with(|s| s.set(seed));
+    GAME_RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+pub fn get_seed() -> u64 {
+    GAME_SEED.with(|seed| seed.get())
+}
+
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+pub fn game_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    GAME_RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
 pub fn rand(max: f32) -> f32 {
-    let mut rng = rand::thread_rng();
-    let random_f: f64 = rng.gen();
+    let random_f: f64 = game_rng(|rng| rng.gen());
     (random_f * max as f64).round() as f32 
 }
 
+// Check if the segment going from a to b crosses the rectangle (Liang-Barsky)
+pub fn segment_intersects_rect(a: Point2<f32>, b: Point2<f32>, rect: Rect) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let p = [-dx, dx, -dy, dy];
+    let q = [a.x - rect.x, rect.x + rect.w - a.x, a.y - rect.y, rect.y + rect.h - a.y];
+    let (mut t0, mut t1) = (0.0_f32, 1.0_f32);
+
+    for i in 0..4 {
+        if p[i] == 0.0 {
+            // Parallel to this edge, and outside of it
+            if q[i] < 0.0 { return false; }
+        } else {
+            let t = q[i] / p[i];
+            if p[i] < 0.0 { t0 = t0.max(t); }
+            else { t1 = t1.min(t); }
+            if t0 > t1 { return false; }
+        }
+    }
+
+    true
+}
+
 pub fn loading_screen(ctx: &mut Context, screen_size: (f32, f32)) {
     ggez::graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, screen_size.0, screen_size.1)).unwrap();
     let shade_rect = graphics::Mesh::new_rectangle(
@@ -100,3 +157,37 @@ pub fn get_credentials() -> Result<Credentials, String> {
 
     Ok(cred)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> Point2<f32> {
+        Point2::new(x, y)
+    }
+
+    #[test]
+    fn segments_through_the_rect() {
+        let rect = Rect::new(10.0, 10.0, 10.0, 10.0);
+        assert!(segment_intersects_rect(point(0.0, 15.0), point(30.0, 15.0), rect));
+        assert!(segment_intersects_rect(point(0.0, 0.0), point(30.0, 30.0), rect));
+        assert!(segment_intersects_rect(point(15.0, 30.0), point(15.0, 0.0), rect));
+        // Only one end in it, or both
+        assert!(segment_intersects_rect(point(15.0, 15.0), point(50.0, 50.0), rect));
+        assert!(segment_intersects_rect(point(12.0, 12.0), point(18.0, 18.0), rect));
+    }
+
+    #[test]
+    fn segments_missing_the_rect() {
+        let rect = Rect::new(10.0, 10.0, 10.0, 10.0);
+        // Stops before it
+        assert!(!segment_intersects_rect(point(0.0, 15.0), point(5.0, 15.0), rect));
+        // Parallel to an edge, outside of it
+        assert!(!segment_intersects_rect(point(0.0, 5.0), point(30.0, 5.0), rect));
+        assert!(!segment_intersects_rect(point(25.0, 0.0), point(25.0, 30.0), rect));
+        // Goes past a corner
+        assert!(!segment_intersects_rect(point(0.0, 15.0), point(15.0, 0.0), rect));
+        // A single point outside of it
+        assert!(!segment_intersects_rect(point(5.0, 5.0), point(5.0, 5.0), rect));
+    }
+}
@@ -0,0 +1,190 @@
+use ggez::{ Context, GameResult, GameError };
+use serde::Deserialize;
+use rand::Rng;
+use crate::bertrand::BertrandKind;
+use crate::utils::game_rng;
+
+// Where the Bertrands of a wave come from
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum SpawnPattern {
+    // Anywhere on the map, not too close to Fabien
+    Random,
+    // All around Fabien, just out of sight
+    Ring,
+    // From around a single spot of the map
+    Cluster,
+    // From the edges of the map
+    Edges
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemyWeight {
+    pub kind: BertrandKind,
+    pub weight: u32
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveDef {
+    // How long the wave lasts, in seconds
+    pub duration: f64,
+    // How many Bertrands can spawn during the wave at most
+    pub spawn_budget: u32,
+    // The lower, the more often Bertrands spawn
+    pub spawn_rate: f32,
+    pub enemy_mix: Vec<EnemyWeight>,
+    pub spawn_pattern: SpawnPattern,
+    // The lower, the more often powerups spawn
+    pub powerup_rate: f32,
+    // Score given when the wave is survived
+    pub bonus: u32,
+    // If set, the wave only ends once all its Bertrands are dead, not
+    // just when its time is up
+    #[serde(default)]
+    pub clear_to_advance: bool
+}
+
+// How the waves after the last defined one are made, based on the last one
+#[derive(Deserialize, Clone, Debug)]
+pub struct EndlessScaling {
+    pub duration: f64,
+    pub spawn_budget_growth: u32,
+    pub spawn_rate_factor: f32,
+    pub powerup_rate_factor: f32,
+    pub bonus_growth: u32
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WaveSchedule {
+    pub waves: Vec<WaveDef>,
+    pub endless: EndlessScaling,
+    // Breather between two waves, in seconds, where nothing spawns
+    #[serde(default = "default_intermission")]
+    pub intermission: f64
+}
+
+fn default_intermission() -> f64 { 5.0 }
+
+// What happened during a wave, shown during the intermission
+pub struct WaveSummary {
+    pub wave: u32,
+    pub kills: u32,
+    // Everything earned during the wave, bonus included
+    pub score: u32,
+    pub bonus: u32
+}
+
+impl WaveSchedule {
+    pub fn load(ctx: &mut Context, path: &str) -> GameResult<WaveSchedule> {
+        let file = ggez::filesystem::open(ctx, path)?;
+        let schedule: WaveSchedule = serde_json::from_reader(file)
+            .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))?;
+        schedule.validate()
+            .map_err(|e| GameError::ResourceLoadError(format!("{} : {}", path, e)))?;
+
+        Ok(schedule)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.waves.is_empty() { return Err(String::from("no wave defined")); }
+
+        // The time of day is worked out from how far into its wave the game is
+        let mut durations = self.waves.iter().map(|wave| wave.duration).chain(std::iter::once(self.endless.duration));
+        if durations.any(|duration| duration <= 0.0 || !duration.is_finite()) {
+            return Err(String::from("wave durations have to be positive"));
+        }
+
+        Ok(())
+    }
+
+    // Waves start at 1. The ones past the schedule are made up here, so
+    // keep the result around instead of asking every frame
+    pub fn get(&self, wave: u32) -> WaveDef {
+        let index = (wave.max(1) - 1) as usize;
+        if index < self.waves.len() {
+            return self.waves[index].clone();
+        }
+
+        let mut def = self.waves.last().unwrap().clone();
+        let extra = (index - self.waves.len() + 1) as u32;
+        def.duration = self.endless.duration;
+        def.spawn_budget += self.endless.spawn_budget_growth * extra;
+        def.spawn_rate *= self.endless.spawn_rate_factor.powi(extra as i32);
+        def.powerup_rate *= self.endless.powerup_rate_factor.powi(extra as i32);
+        def.bonus += self.endless.bonus_growth * extra;
+
+        def
+    }
+}
+
+impl WaveDef {
+    pub fn pick_kind(&self) -> BertrandKind {
+        let total: u32 = self.enemy_mix.iter().map(|e| e.weight).sum();
+        if total == 0 { return BertrandKind::Regular; }
+
+        let mut roll = game_rng(|rng| rng.gen_range(0..total));
+        for enemy in self.enemy_mix.iter() {
+            if roll < enemy.weight { return enemy.kind; }
+            roll -= enemy.weight;
+        }
+
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(first_duration: f64) -> WaveSchedule {
+        serde_json::from_str(&format!(r#"{{
+            "waves": [
+                {{ "duration": {}, "spawn_budget": 10, "spawn_rate": 100.0,
+                   "enemy_mix": [ {{ "kind": "Regular", "weight": 1 }} ],
+                   "spawn_pattern": "Random", "powerup_rate": 1000.0, "bonus": 10 }},
+                {{ "duration": 45.0, "spawn_budget": 20, "spawn_rate": 50.0,
+                   "enemy_mix": [ {{ "kind": "Regular", "weight": 1 }} ],
+                   "spawn_pattern": "Ring", "powerup_rate": 800.0, "bonus": 20, "clear_to_advance": true }}
+            ],
+            "endless": {{
+                "duration": 60.0, "spawn_budget_growth": 5, "spawn_rate_factor": 0.5,
+                "powerup_rate_factor": 0.8, "bonus_growth": 10
+            }}
+        }}"#, first_duration)).unwrap()
+    }
+
+    #[test]
+    fn defined_waves_are_given_as_they_are() {
+        let schedule = schedule(30.0);
+        assert_eq!(schedule.get(1).spawn_budget, 10);
+        assert_eq!(schedule.get(2).duration, 45.0);
+        assert_eq!(schedule.get(2).spawn_pattern, SpawnPattern::Ring);
+        // There's no wave 0, it's the first one
+        assert_eq!(schedule.get(0).spawn_budget, 10);
+        assert_eq!(schedule.intermission, 5.0);
+    }
+
+    #[test]
+    fn endless_waves_scale_from_the_last_one() {
+        let schedule = schedule(30.0);
+
+        let third = schedule.get(3);
+        assert_eq!(third.duration, 60.0);
+        assert_eq!(third.spawn_budget, 25);
+        assert_eq!(third.spawn_rate, 25.0);
+        assert_eq!(third.bonus, 30);
+        assert!(third.clear_to_advance);
+
+        let fifth = schedule.get(5);
+        assert_eq!(fifth.spawn_budget, 35);
+        assert_eq!(fifth.spawn_rate, 50.0 * 0.5_f32.powi(3));
+        assert!((fifth.powerup_rate - 800.0 * 0.8_f32.powi(3)).abs() < 1e-3);
+        assert_eq!(fifth.bonus, 50);
+    }
+
+    #[test]
+    fn waves_have_to_last() {
+        assert!(schedule(30.0).validate().is_ok());
+        assert!(schedule(0.0).validate().is_err());
+        assert!(schedule(-5.0).validate().is_err());
+    }
+}